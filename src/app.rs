@@ -1,5 +1,27 @@
-use ratatui::widgets::ListState;
-use std::{fs, io, path::Path};
+use crate::{
+    browser::TableBrowser,
+    config::{Database, PaneLayout},
+    db::{
+        database_directive, open_database, OpenTransaction, PendingRun, ResultSet, RunningQuery,
+        StatementOutput, StatementResult,
+    },
+    history::{History, HistoryEntry, HISTORY_PAGE_SIZE},
+    keys::{Action, KeyMap},
+    output::{plain_text, tsv_row, write_result_set, OutputFormat},
+    params::{load_saved_parameters, parse_parameter_value, save_parameters, SavedParameters},
+    schema::{load_schema, select_template, SchemaObject},
+    search::{fuzzy_match, fuzzy_match_lines},
+    theme::Theme,
+    value::{render_inline, ValueFormat},
+    watcher::ScriptWatcher,
+};
+use ratatui::widgets::{ListState, TableState};
+use rusqlite::types::Value;
+use std::{
+    collections::{HashMap, HashSet},
+    fs, io,
+    path::{Path, PathBuf},
+};
 
 #[derive(Clone, Copy, PartialEq, Debug)]
 pub enum InputMode {
@@ -8,67 +30,321 @@ pub enum InputMode {
     ConfirmingDelete,
     RenamingScript,
     ShowHelp,
+    Inspecting,
+    EnteringParameters,
+    Exporting,
+    BrowsingHistory,
+    PickingDatabase,
+    FilteringColumn,
+    SearchingScripts,
+    CreatingFolder,
+    ConfirmingRun,
+    ReviewingTransaction,
+}
+
+/// Which pane receives navigation keys in Normal mode
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum Focus {
+    Scripts,
+    Results,
+}
+
+/// Which list the left pane is showing
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum LeftPane {
+    Scripts,
+    Schema,
+}
+
+/// What the inspector popup is showing
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum InspectTarget {
+    Cell,
+    Row,
+}
+
+/// State of the cell/row inspector popup
+#[derive(Debug)]
+pub struct Inspector {
+    pub target: InspectTarget,
+    pub scroll: u16,
+}
+
+/// What a yank keybinding copies from the results grid
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum YankTarget {
+    Cell,
+    Row,
+    ResultCsv,
+    ResultMarkdown,
+}
+
+/// Values being typed into the parameter popup before a script runs
+#[derive(Debug, Default)]
+pub struct ParameterForm {
+    pub script_path: String,
+    /// `(parameter name, typed value)` pairs, in order of first use in the script
+    pub fields: Vec<(String, String)>,
+    pub selected: usize,
+}
+
+impl ParameterForm {
+    /// The typed values, converted for binding
+    pub fn values(&self) -> HashMap<String, Value> {
+        self.fields
+            .iter()
+            .map(|(name, input)| (name.clone(), parse_parameter_value(input)))
+            .collect()
+    }
+
+    pub fn selected_input(&mut self) -> Option<&mut String> {
+        self.fields.get_mut(self.selected).map(|(_, input)| input)
+    }
+
+    pub fn next_field(&mut self) {
+        if !self.fields.is_empty() {
+            self.selected = (self.selected + 1) % self.fields.len();
+        }
+    }
+
+    pub fn previous_field(&mut self) {
+        if !self.fields.is_empty() {
+            self.selected = (self.selected + self.fields.len() - 1) % self.fields.len();
+        }
+    }
+}
+
+/// Cursor and scroll position inside the results grid
+#[derive(Default, Debug)]
+pub struct ResultView {
+    pub row: usize,
+    pub column: usize,
+    pub row_offset: usize,
+    pub column_offset: usize,
+    /// Natural display width of each column, before capping to the pane
+    pub column_widths: Vec<usize>,
+    /// Number of data rows visible in the last drawn frame
+    pub page_height: usize,
 }
 
 /// App holds the state of the application
 pub struct App {
+    /// Rows shown in the list: the folder tree, or the scripts matching `search_query`
     pub sql_files: Vec<String>,
+    /// Every script under the script directory, at any depth
+    pub all_sql_files: Vec<String>,
+    /// Every folder under the script directory, at any depth
+    pub script_folders: Vec<String>,
+    pub collapsed_folders: HashSet<String>,
+    pub search_query: String,
+    /// Whether the search also looks inside each script's SQL
+    pub search_contents: bool,
+    /// Char positions in each listed script's stem that matched the search
+    pub search_matches: HashMap<String, Vec<usize>>,
+    /// Database named by each script's `-- @db:` directive, keyed by script path
+    pub script_databases: HashMap<String, String>,
     pub list_state: ListState,
     pub query_result: String,
+    /// The status text of the last failed run, drawn in the error colour until replaced
+    pub failed_status: Option<String>,
+    pub results: Vec<StatementResult>,
+    pub result_index: usize,
+    pub result_view: ResultView,
+    /// Shows the `EXPLAIN` bytecode instead of the query plan tree for explained statements
+    pub show_bytecode: bool,
+    /// Set while the results grid shows a table opened from the schema browser
+    pub table_browser: Option<TableBrowser>,
+    pub focus: Focus,
+    pub left_pane: LeftPane,
+    /// Objects of the active database, loaded when the schema tab is opened
+    pub schema: Vec<SchemaObject>,
+    pub schema_state: ListState,
+    pub inspector: Inspector,
+    pub parameter_form: ParameterForm,
+    pub saved_parameters: SavedParameters,
+    pub parameters_path: PathBuf,
+    pub export_format: OutputFormat,
+    pub history: Option<History>,
+    pub history_entries: Vec<HistoryEntry>,
+    pub history_state: TableState,
+    pub running: Option<RunningQuery>,
+    /// A script waiting on the modification prompt
+    pub pending_run: Option<PendingRun>,
+    /// Open while a script run in a transaction waits to be committed or rolled back
+    pub transaction: Option<OpenTransaction>,
+    /// Opens every connection read-only, so SQLite refuses any write
+    pub read_only: bool,
+    pub databases: Vec<Database>,
+    pub active_database: usize,
+    /// Highlighted entry in the database picker
+    pub database_state: ListState,
+    pub script_dir_path: PathBuf,
+    /// Reports outside changes to the script directory; `None` if watching isn't supported
+    pub script_watcher: Option<ScriptWatcher>,
     pub script_content_preview: String,
+    /// Script the preview is showing, so reloading it keeps the scroll position
+    pub preview_path: Option<String>,
+    /// First preview line shown
+    pub preview_scroll: usize,
+    /// Number of preview lines visible in the last drawn frame
+    pub preview_height: usize,
     pub input_mode: InputMode,
     pub filename_input: String,
+    /// Initial contents of the script created by the new-script popup
+    pub new_script_template: String,
     pub help_message: String,
+    /// First help line shown, for terminals too short for all of it
+    pub help_scroll: u16,
+    pub keymap: KeyMap,
+    pub theme: Theme,
+    pub layout: PaneLayout,
+    pub value_format: ValueFormat,
 }
 
 impl App {
     /// Creates a new App, scanning the configured script directory for .sql files
-    pub fn new(script_dir_path: &Path, db_path: &Path) -> io::Result<Self> {
-        let help_message = format!(
-            "Welcome to sqledger!\n\nScripts: {}\nDatabase: {}\n\n--- Keybinds ---\n'j'/'k' or ↓/↑: Navigate scripts\n'l' or 'Enter' : Run selected script\n'e'              : Edit selected script\n'a'              : Add a new script\n'd'              : Delete selected script\n'r'              : Rename selected script\n'?'              : Toggle this help message\n'q'              : Quit",
-            script_dir_path.display(),
-            db_path.display()
-        );
+    pub fn new(
+        script_dir_path: &Path,
+        databases: Vec<Database>,
+        active_database: usize,
+        parameters_path: &Path,
+        history_path: &Path,
+        value_format: ValueFormat,
+        keymap: KeyMap,
+    ) -> io::Result<Self> {
+        let help_message = help_message(script_dir_path, &databases[active_database], &keymap);
+        let (history, mut welcome) = match History::open(history_path) {
+            Ok(history) => (
+                Some(history),
+                format!(
+                    "Welcome! Press '{}' for help.",
+                    keymap.describe(Action::Help)
+                ),
+            ),
+            Err(e) => (
+                None,
+                format!(
+                    "Could not open run history at {}: {}. Runs will not be recorded.",
+                    history_path.display(),
+                    e
+                ),
+            ),
+        };
+        let script_watcher = match ScriptWatcher::new(script_dir_path) {
+            Ok(watcher) => Some(watcher),
+            Err(e) => {
+                welcome.push_str(&format!(
+                    " (Not watching the script directory for changes: {})",
+                    e
+                ));
+                None
+            }
+        };
         let mut app = Self {
             sql_files: Vec::new(),
+            all_sql_files: Vec::new(),
+            script_folders: Vec::new(),
+            collapsed_folders: HashSet::new(),
+            search_query: String::new(),
+            search_contents: false,
+            search_matches: HashMap::new(),
+            script_databases: HashMap::new(),
             list_state: ListState::default(),
-            query_result: "Welcome! Press '?' for help.".to_string(),
+            query_result: welcome,
+            failed_status: None,
+            results: Vec::new(),
+            result_index: 0,
+            result_view: ResultView::default(),
+            show_bytecode: false,
+            table_browser: None,
+            focus: Focus::Scripts,
+            left_pane: LeftPane::Scripts,
+            schema: Vec::new(),
+            schema_state: ListState::default(),
+            inspector: Inspector {
+                target: InspectTarget::Cell,
+                scroll: 0,
+            },
+            parameter_form: ParameterForm::default(),
+            saved_parameters: load_saved_parameters(parameters_path),
+            parameters_path: parameters_path.to_path_buf(),
+            export_format: OutputFormat::Csv,
+            history,
+            history_entries: Vec::new(),
+            history_state: TableState::default(),
+            running: None,
+            pending_run: None,
+            transaction: None,
+            read_only: false,
+            databases,
+            active_database,
+            database_state: ListState::default(),
+            script_dir_path: script_dir_path.to_path_buf(),
+            script_watcher,
             script_content_preview: "".to_string(),
+            preview_path: None,
+            preview_scroll: 0,
+            preview_height: 0,
             input_mode: InputMode::Normal,
             filename_input: String::new(),
+            new_script_template: String::new(),
             help_message,
+            help_scroll: 0,
+            keymap,
+            theme: Theme::default(),
+            layout: PaneLayout::default(),
+            value_format,
         };
         app.rescan_scripts(script_dir_path)?;
         Ok(app)
     }
 
     pub fn rescan_scripts(&mut self, script_dir_path: &Path) -> io::Result<()> {
-        let mut sql_files = Vec::new();
-        let script_dir_entries = match fs::read_dir(script_dir_path) {
-            Ok(entries) => entries,
-            Err(e) => {
-                return Err(io::Error::new(
-                    e.kind(),
-                    format!(
-                        "Failed to read script directory at: {}. \nError: {}",
-                        script_dir_path.display(),
-                        e
-                    ),
-                ));
-            }
-        };
-
-        sql_files.extend(
-            script_dir_entries
-                .flatten()
-                .map(|entry| entry.path())
-                .filter(|path| path.is_file())
-                .filter(|path| path.extension().is_some_and(|ext| ext == "sql"))
-                .map(|path| path.to_string_lossy().to_string()),
-        );
+        let mut folders = Vec::new();
+        let mut scripts = Vec::new();
+        if let Err(e) = walk_script_dir(script_dir_path, &mut folders, &mut scripts) {
+            return Err(io::Error::new(
+                e.kind(),
+                format!(
+                    "Failed to read script directory at: {}. \nError: {}",
+                    script_dir_path.display(),
+                    e
+                ),
+            ));
+        }
+        let mut sql_files: Vec<String> = scripts
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
         sql_files.sort();
+        self.script_folders = folders
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect();
+        self.script_folders.sort();
+
+        self.script_databases = sql_files
+            .iter()
+            .filter_map(|path| {
+                let sql_content = fs::read_to_string(path).ok()?;
+                let name = database_directive(&sql_content)?;
+                Some((path.clone(), name.to_string()))
+            })
+            .collect();
+        self.all_sql_files = sql_files;
 
-        self.sql_files = sql_files;
+        // Keep the same script or folder selected, even if rows above it came or went
+        let selected_path = self
+            .list_state
+            .selected()
+            .and_then(|i| self.sql_files.get(i))
+            .cloned();
+        self.sql_files = self.matching_scripts();
+        if let Some(index) =
+            selected_path.and_then(|path| self.sql_files.iter().position(|p| *p == path))
+        {
+            self.list_state.select(Some(index));
+        }
 
         let mut valid_selection_exists = false;
         if let Some(selected_index) = self.list_state.selected() {
@@ -87,6 +363,174 @@ impl App {
         Ok(())
     }
 
+    /// List rows for the folder tree: each folder's subfolders, then its scripts,
+    /// skipping the contents of collapsed folders
+    fn tree_rows(&self, folder: &Path, rows: &mut Vec<String>) {
+        for sub_folder in &self.script_folders {
+            if Path::new(sub_folder).parent() == Some(folder) {
+                rows.push(sub_folder.clone());
+                if !self.collapsed_folders.contains(sub_folder) {
+                    self.tree_rows(Path::new(sub_folder), rows);
+                }
+            }
+        }
+        rows.extend(
+            self.all_sql_files
+                .iter()
+                .filter(|script| Path::new(script).parent() == Some(folder))
+                .cloned(),
+        );
+    }
+
+    pub fn is_folder(&self, path: &str) -> bool {
+        self.script_folders.iter().any(|folder| folder == path)
+    }
+
+    /// The selected row, if it is a script rather than a folder
+    pub fn selected_script(&self) -> Option<&String> {
+        let path = self.sql_files.get(self.list_state.selected()?)?;
+        (!self.is_folder(path)).then_some(path)
+    }
+
+    /// Where new scripts and folders go: the selected folder, or the selected script's folder
+    pub fn selected_folder(&self) -> PathBuf {
+        match self
+            .list_state
+            .selected()
+            .and_then(|i| self.sql_files.get(i))
+        {
+            Some(path) if self.is_folder(path) => PathBuf::from(path),
+            Some(path) => Path::new(path)
+                .parent()
+                .unwrap_or(&self.script_dir_path)
+                .to_path_buf(),
+            None => self.script_dir_path.clone(),
+        }
+    }
+
+    /// Expands or collapses the selected folder
+    pub fn toggle_selected_folder(&mut self) {
+        let Some(path) = self
+            .list_state
+            .selected()
+            .and_then(|i| self.sql_files.get(i))
+        else {
+            return;
+        };
+        if !self.is_folder(path) {
+            return;
+        }
+        let path = path.clone();
+        if !self.collapsed_folders.remove(&path) {
+            self.collapsed_folders.insert(path);
+        }
+        self.apply_script_search();
+    }
+
+    /// Collapses the folder containing the selection (or the selected folder) and selects it
+    pub fn collapse_selected_folder(&mut self) {
+        let Some(path) = self
+            .list_state
+            .selected()
+            .and_then(|i| self.sql_files.get(i))
+        else {
+            return;
+        };
+        let folder = if self.is_folder(path) && !self.collapsed_folders.contains(path) {
+            path.clone()
+        } else {
+            match Path::new(path).parent() {
+                Some(parent) if parent != self.script_dir_path => {
+                    parent.to_string_lossy().to_string()
+                }
+                _ => return,
+            }
+        };
+        self.collapsed_folders.insert(folder.clone());
+        self.select_path(&folder);
+    }
+
+    /// Selects a script or folder by path, expanding the folders above it
+    pub fn select_path(&mut self, path: &str) {
+        let mut ancestor = Path::new(path).parent();
+        while let Some(folder) = ancestor {
+            self.collapsed_folders
+                .remove(folder.to_string_lossy().as_ref());
+            ancestor = folder.parent();
+        }
+        self.sql_files = self.matching_scripts();
+        if let Some(index) = self.sql_files.iter().position(|p| p == path) {
+            self.list_state.select(Some(index));
+        }
+        self.update_preview();
+    }
+
+    /// The scripts matching the search, best match first; every script when there is no search
+    fn matching_scripts(&mut self) -> Vec<String> {
+        self.search_matches.clear();
+        if self.search_query.is_empty() {
+            let mut rows = Vec::new();
+            self.tree_rows(&self.script_dir_path, &mut rows);
+            return rows;
+        }
+        let mut scored = Vec::new();
+        for path in &self.all_sql_files {
+            let stem = Path::new(path)
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().to_string())
+                .unwrap_or_default();
+            if let Some(stem_match) = fuzzy_match(&self.search_query, &stem) {
+                // Name matches always rank above matches found only in the contents
+                scored.push((stem_match.score + 1000, path.clone()));
+                self.search_matches
+                    .insert(path.clone(), stem_match.positions);
+            } else if self.search_contents {
+                let contents = fs::read_to_string(path).unwrap_or_default();
+                if let Some(content_match) = fuzzy_match_lines(&self.search_query, &contents) {
+                    scored.push((content_match.score, path.clone()));
+                }
+            }
+        }
+        // Stable sort, so equal scores stay in name order
+        scored.sort_by_key(|(score, _)| std::cmp::Reverse(*score));
+        scored.into_iter().map(|(_, path)| path).collect()
+    }
+
+    /// Re-filters the list after the search changed, keeping the selected script if it still matches
+    pub fn apply_script_search(&mut self) {
+        let selected_path = self
+            .list_state
+            .selected()
+            .and_then(|i| self.sql_files.get(i))
+            .cloned();
+        self.sql_files = self.matching_scripts();
+        let index = selected_path
+            .and_then(|path| self.sql_files.iter().position(|p| *p == path))
+            .or(if self.sql_files.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+        self.list_state.select(index);
+        self.update_preview();
+    }
+
+    pub fn start_script_search(&mut self) {
+        self.left_pane = LeftPane::Scripts;
+        self.input_mode = InputMode::SearchingScripts;
+    }
+
+    pub fn toggle_search_contents(&mut self) {
+        self.search_contents = !self.search_contents;
+        self.apply_script_search();
+    }
+
+    /// Drops the search and shows every script again
+    pub fn clear_script_search(&mut self) {
+        self.search_query.clear();
+        self.apply_script_search();
+    }
+
     pub fn get_selected_filename_stem(&self) -> Option<String> {
         self.list_state
             .selected()
@@ -136,14 +580,666 @@ impl App {
         self.update_preview();
     }
 
+    /// Replaces the results of the last run and shows the first statement's output
+    pub fn set_results(&mut self, results: Vec<StatementResult>) {
+        self.results = results;
+        self.table_browser = None;
+        self.select_result(0);
+    }
+
+    /// Opens the selected table or view from the schema browser in the results grid
+    pub fn open_table_browser(&mut self) {
+        let Some(object) = self.selected_schema_object() else {
+            return;
+        };
+        if !object.is_relation() {
+            self.query_result = format!("{} is not a table or view.", object.name);
+            return;
+        }
+        let mut browser = TableBrowser {
+            db_path: self.active_database().path.clone(),
+            read_only: self.read_only,
+            table: object.name.clone(),
+            columns: Vec::new(),
+            sort: None,
+            filters: Vec::new(),
+            exhausted: false,
+        };
+        match browser.fetch(0) {
+            Ok(page) => {
+                browser.columns = page.columns.clone();
+                browser.filters = vec![String::new(); page.columns.len()];
+                self.show_browser_page(browser, page);
+                self.focus = Focus::Results;
+            }
+            Err(e) => self.query_result = format!("Error reading {}: {}", browser.table, e),
+        }
+    }
+
+    /// Shows the first page of a browsed table, replacing the current results
+    fn show_browser_page(&mut self, browser: TableBrowser, page: ResultSet) {
+        let sql = browser.query().map(|(sql, _)| sql).unwrap_or_default();
+        self.set_results(vec![StatementResult {
+            sql,
+            output: StatementOutput::Rows(page),
+            stats: None,
+        }]);
+        self.table_browser = Some(browser);
+        self.update_browser_status();
+    }
+
+    /// Re-runs the browse query from the first row after the sort or a filter changed
+    fn refresh_table_browser(&mut self) {
+        let Some(mut browser) = self.table_browser.take() else {
+            return;
+        };
+        match browser.fetch(0) {
+            Ok(page) => {
+                let column = self.result_view.column;
+                self.show_browser_page(browser, page);
+                self.result_view.column = column;
+            }
+            Err(e) => {
+                self.query_result = format!("Error reading {}: {}", browser.table, e);
+                self.table_browser = Some(browser);
+            }
+        }
+    }
+
+    /// Fetches the next page once the cursor reaches the last loaded row
+    fn load_more_browser_rows(&mut self) {
+        let loaded = self.current_result_set().map_or(0, |rs| rs.rows.len());
+        let Some(browser) = &mut self.table_browser else {
+            return;
+        };
+        if browser.exhausted || self.result_view.row + 1 < loaded {
+            return;
+        }
+        match browser.fetch(loaded) {
+            Ok(page) => {
+                if let Some(StatementResult {
+                    output: StatementOutput::Rows(result_set),
+                    ..
+                }) = self.results.get_mut(self.result_index)
+                {
+                    result_set.rows.extend(page.rows);
+                }
+                if let Some(result_set) = self.current_result_set() {
+                    self.result_view.column_widths =
+                        measure_columns(result_set, &self.value_format);
+                }
+                self.update_browser_status();
+            }
+            Err(e) => self.query_result = format!("Error reading {}: {}", browser.table, e),
+        }
+    }
+
+    fn update_browser_status(&mut self) {
+        let Some(browser) = &self.table_browser else {
+            return;
+        };
+        let loaded = self.current_result_set().map_or(0, |rs| rs.rows.len());
+        let more = if browser.exhausted { "" } else { "+" };
+        let details = browser.describe();
+        self.query_result = if details.is_empty() {
+            format!("{}: {}{} rows", browser.table, loaded, more)
+        } else {
+            format!("{}: {}{} rows, {}", browser.table, loaded, more, details)
+        };
+    }
+
+    /// Cycles the sort on the cursor column of a browsed table
+    pub fn cycle_browser_sort(&mut self) {
+        let column = self.result_view.column;
+        if let Some(browser) = &mut self.table_browser {
+            browser.cycle_sort(column);
+            self.refresh_table_browser();
+        }
+    }
+
+    /// Opens the filter popup for the cursor column of a browsed table
+    pub fn open_column_filter(&mut self) {
+        let column = self.result_view.column;
+        if let Some(filter) = self
+            .table_browser
+            .as_ref()
+            .and_then(|browser| browser.filters.get(column))
+        {
+            self.filename_input = filter.clone();
+            self.input_mode = InputMode::FilteringColumn;
+        }
+    }
+
+    /// Applies the typed filter to the cursor column; an empty filter removes it
+    pub fn apply_column_filter(&mut self) {
+        self.input_mode = InputMode::Normal;
+        let column = self.result_view.column;
+        let Some(browser) = &mut self.table_browser else {
+            return;
+        };
+        let previous = std::mem::replace(
+            &mut browser.filters[column],
+            self.filename_input.trim().to_string(),
+        );
+        if let Err(e) = browser.query() {
+            browser.filters[column] = previous;
+            self.query_result = e;
+            return;
+        }
+        self.refresh_table_browser();
+    }
+
+    /// Removes every filter from a browsed table
+    pub fn clear_column_filters(&mut self) {
+        if let Some(browser) = &mut self.table_browser {
+            browser.filters.iter_mut().for_each(String::clear);
+            self.refresh_table_browser();
+        }
+    }
+
+    pub fn current_result(&self) -> Option<&StatementResult> {
+        self.results.get(self.result_index)
+    }
+
+    pub fn current_result_set(&self) -> Option<&ResultSet> {
+        match self.current_result().map(|result| &result.output) {
+            Some(StatementOutput::Rows(result_set)) => Some(result_set),
+            Some(StatementOutput::Plan(plan)) if self.show_bytecode => Some(&plan.bytecode),
+            _ => None,
+        }
+    }
+
+    /// Switches explained statements between the query plan tree and the bytecode grid
+    pub fn toggle_bytecode(&mut self) {
+        if !matches!(
+            self.current_result().map(|result| &result.output),
+            Some(StatementOutput::Plan(_))
+        ) {
+            return;
+        }
+        self.show_bytecode = !self.show_bytecode;
+        self.select_result(self.result_index);
+    }
+
+    pub fn next_result(&mut self) {
+        if self.result_index + 1 < self.results.len() {
+            self.select_result(self.result_index + 1);
+        }
+    }
+
+    pub fn previous_result(&mut self) {
+        self.select_result(self.result_index.saturating_sub(1));
+    }
+
+    /// Shows the statement result at `index` and resets the grid cursor
+    fn select_result(&mut self, index: usize) {
+        self.result_index = index;
+        let column_widths = self
+            .current_result_set()
+            .map(|result_set| measure_columns(result_set, &self.value_format))
+            .unwrap_or_default();
+        self.result_view = ResultView {
+            column_widths,
+            page_height: self.result_view.page_height,
+            ..ResultView::default()
+        };
+    }
+
+    /// Opens the inspector popup on the cursor cell or row, if there is one
+    pub fn open_inspector(&mut self, target: InspectTarget) {
+        let has_rows = self
+            .current_result_set()
+            .is_some_and(|result_set| !result_set.rows.is_empty());
+        if has_rows {
+            self.inspector = Inspector { target, scroll: 0 };
+            self.input_mode = InputMode::Inspecting;
+        }
+    }
+
+    /// Opens the parameter popup for a script, prefilled with the values used last time
+    pub fn open_parameter_form(&mut self, script_path: String, names: Vec<String>) {
+        let remembered = self.saved_parameters.get(&script_path);
+        let fields = names
+            .into_iter()
+            .map(|name| {
+                let value = remembered
+                    .and_then(|values| values.get(&name))
+                    .cloned()
+                    .unwrap_or_default();
+                (name, value)
+            })
+            .collect();
+        self.parameter_form = ParameterForm {
+            script_path,
+            fields,
+            selected: 0,
+        };
+        self.input_mode = InputMode::EnteringParameters;
+        self.query_result = format!(
+            "Fill in the script parameters. {}",
+            self.keymap.input_prompt("run")
+        );
+    }
+
+    /// Stores the form's values as the script's last-used parameters, on disk as well
+    pub fn remember_parameters(&mut self) -> io::Result<()> {
+        let values = self.parameter_form.fields.iter().cloned().collect();
+        self.saved_parameters
+            .insert(self.parameter_form.script_path.clone(), values);
+        save_parameters(&self.parameters_path, &self.saved_parameters)
+    }
+
+    /// Default export target: `exports/<script>.<ext>` beside the scripts directory
+    pub fn default_export_path(&self, script_dir_path: &Path) -> PathBuf {
+        let stem = self
+            .get_selected_filename_stem()
+            .unwrap_or_else(|| "result".to_string());
+        script_dir_path
+            .parent()
+            .unwrap_or(script_dir_path)
+            .join("exports")
+            .join(format!("{}.{}", stem, self.export_format.extension()))
+    }
+
+    /// Writes the result set on screen to `path` in the selected export format
+    pub fn export_current_result(&self, path: &Path) -> io::Result<usize> {
+        let result_set = self
+            .current_result_set()
+            .ok_or_else(|| io::Error::other("No result set to export"))?;
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        let table_name = self
+            .get_selected_filename_stem()
+            .unwrap_or_else(|| "result".to_string());
+        let mut file = io::BufWriter::new(fs::File::create(path)?);
+        write_result_set(
+            &mut file,
+            result_set,
+            self.export_format,
+            &table_name,
+            &self.value_format,
+        )?;
+        io::Write::flush(&mut file)?;
+        Ok(result_set.rows.len())
+    }
+
+    /// Text for a yank keybinding, with a short description of what it holds
+    pub fn yank_text(&self, target: YankTarget) -> Option<(String, String)> {
+        let result_set = self.current_result_set()?;
+        let view = &self.result_view;
+        match target {
+            YankTarget::Cell => {
+                let value = result_set.rows.get(view.row)?.get(view.column)?;
+                let text = plain_text(value);
+                Some((text, format!("'{}' cell", result_set.columns[view.column])))
+            }
+            YankTarget::Row => {
+                let row = result_set.rows.get(view.row)?;
+                let text = tsv_row(row);
+                Some((text, format!("row {} as TSV", view.row + 1)))
+            }
+            YankTarget::ResultCsv | YankTarget::ResultMarkdown => {
+                let format = if target == YankTarget::ResultCsv {
+                    OutputFormat::Csv
+                } else {
+                    OutputFormat::Markdown
+                };
+                let mut buffer = Vec::new();
+                write_result_set(&mut buffer, result_set, format, "", &self.value_format).ok()?;
+                let text = String::from_utf8_lossy(&buffer).to_string();
+                let description = format!("{} rows as {}", result_set.rows.len(), format.label());
+                Some((text, description))
+            }
+        }
+    }
+
+    /// Loads recent runs from the ledger and opens the history pane
+    pub fn open_history(&mut self) {
+        let Some(history) = &self.history else {
+            self.query_result = "Run history is not available.".to_string();
+            return;
+        };
+        match history.recent(HISTORY_PAGE_SIZE) {
+            Ok(entries) => {
+                self.history_state
+                    .select(if entries.is_empty() { None } else { Some(0) });
+                self.history_entries = entries;
+                self.input_mode = InputMode::BrowsingHistory;
+            }
+            Err(e) => self.query_result = format!("Error reading run history: {}", e),
+        }
+    }
+
+    pub fn next_history_entry(&mut self) {
+        if let Some(i) = self.history_state.selected() {
+            if i + 1 < self.history_entries.len() {
+                self.history_state.select(Some(i + 1));
+            }
+        }
+    }
+
+    pub fn previous_history_entry(&mut self) {
+        if let Some(i) = self.history_state.selected() {
+            self.history_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    /// The exact SQL text executed by the selected history entry
+    pub fn selected_history_sql(&self) -> Option<(&HistoryEntry, String)> {
+        let entry = self.history_entries.get(self.history_state.selected()?)?;
+        let sql = self.history.as_ref()?.sql_text(&entry.sql_hash).ok()??;
+        Some((entry, sql))
+    }
+
+    pub fn active_database(&self) -> &Database {
+        &self.databases[self.active_database]
+    }
+
+    /// Whether a script's `-- @db:` directive names a configured database
+    pub fn is_known_database(&self, name: &str) -> bool {
+        self.databases.iter().any(|db| db.name == name)
+    }
+
+    /// Shows `message` in the status line as a failure
+    pub fn report_failure(&mut self, message: String) {
+        self.failed_status = Some(message.clone());
+        self.query_result = message;
+    }
+
+    /// Whether the status line still shows the last failure
+    pub fn status_is_failure(&self) -> bool {
+        self.failed_status.as_deref() == Some(self.query_result.as_str())
+    }
+
+    /// Shows the transaction prompt instead of quitting, since quitting would roll back
+    pub fn warn_open_transaction(&mut self) {
+        self.input_mode = InputMode::ReviewingTransaction;
+        self.query_result =
+            "A transaction is still open. Commit or roll it back before quitting.".to_string();
+    }
+
+    /// Switches read-only mode, which applies from the next connection opened
+    pub fn toggle_read_only(&mut self) {
+        self.read_only = !self.read_only;
+        self.query_result = if self.read_only {
+            "Read-only mode on: scripts can't modify the database.".to_string()
+        } else {
+            "Read-only mode off: modifying scripts ask for confirmation.".to_string()
+        };
+    }
+
+    /// Opens the database picker with the active database highlighted
+    pub fn open_database_picker(&mut self) {
+        self.database_state.select(Some(self.active_database));
+        self.input_mode = InputMode::PickingDatabase;
+    }
+
+    pub fn next_database(&mut self) {
+        if let Some(i) = self.database_state.selected() {
+            self.database_state
+                .select(Some((i + 1).min(self.databases.len() - 1)));
+        }
+    }
+
+    pub fn previous_database(&mut self) {
+        if let Some(i) = self.database_state.selected() {
+            self.database_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    /// Makes the database highlighted in the picker the one scripts run against
+    pub fn switch_to_selected_database(&mut self) {
+        self.input_mode = InputMode::Normal;
+        if self.running.is_some() {
+            self.query_result =
+                "Wait for the running script to finish before switching databases.".to_string();
+            return;
+        }
+        let Some(index) = self.database_state.selected() else {
+            return;
+        };
+        self.active_database = index;
+        let database = &self.databases[index];
+        self.query_result = format!(
+            "Switched to database {} ({}).",
+            database.name,
+            database.path.display()
+        );
+        self.help_message = help_message(&self.script_dir_path, database, &self.keymap);
+        if self.left_pane == LeftPane::Schema {
+            self.reload_schema();
+        }
+    }
+
+    /// Switches the left pane between the script list and the schema browser
+    pub fn toggle_left_pane(&mut self) {
+        self.left_pane = match self.left_pane {
+            LeftPane::Scripts => {
+                self.reload_schema();
+                LeftPane::Schema
+            }
+            LeftPane::Schema => LeftPane::Scripts,
+        };
+    }
+
+    /// Re-reads the schema of the active database, keeping the selection by name
+    pub fn reload_schema(&mut self) {
+        let selected_name = self.selected_schema_object().map(|o| o.name.clone());
+        let database = self.active_database();
+        let loaded =
+            open_database(&database.path, self.read_only).and_then(|conn| load_schema(&conn));
+        match loaded {
+            Ok(schema) => self.schema = schema,
+            Err(e) => {
+                self.schema.clear();
+                self.query_result = format!(
+                    "Error reading schema of {}: {}",
+                    self.active_database().name,
+                    e
+                );
+            }
+        }
+        let index = selected_name
+            .and_then(|name| self.schema.iter().position(|o| o.name == name))
+            .or(if self.schema.is_empty() {
+                None
+            } else {
+                Some(0)
+            });
+        self.schema_state.select(index);
+    }
+
+    pub fn selected_schema_object(&self) -> Option<&SchemaObject> {
+        self.schema.get(self.schema_state.selected()?)
+    }
+
+    pub fn next_schema_object(&mut self) {
+        if let Some(i) = self.schema_state.selected() {
+            if i + 1 < self.schema.len() {
+                self.schema_state.select(Some(i + 1));
+            }
+        }
+    }
+
+    pub fn previous_schema_object(&mut self) {
+        if let Some(i) = self.schema_state.selected() {
+            self.schema_state.select(Some(i.saturating_sub(1)));
+        }
+    }
+
+    /// Opens the new-script popup pre-filled with a `SELECT` from the selected table or view
+    pub fn new_script_from_schema(&mut self) {
+        let Some(object) = self.selected_schema_object() else {
+            return;
+        };
+        if !object.is_relation() {
+            self.query_result = format!("{} is not a table or view.", object.name);
+            return;
+        }
+        let name = object.name.clone();
+        self.new_script_template = select_template(&name);
+        self.filename_input = name;
+        self.input_mode = InputMode::EditingFilename;
+        self.query_result = format!(
+            "Enter new script name (no extension). {}",
+            self.keymap.input_prompt("confirm")
+        );
+    }
+
+    /// Scrolls the preview by half a pane; the drawing code clamps it to the script's length
+    pub fn scroll_preview(&mut self, down: bool) {
+        let step = (self.preview_height / 2).max(1);
+        self.preview_scroll = if down {
+            self.preview_scroll + step
+        } else {
+            self.preview_scroll.saturating_sub(step)
+        };
+    }
+
+    pub fn toggle_focus(&mut self) {
+        self.focus = match self.focus {
+            Focus::Scripts => Focus::Results,
+            Focus::Results => Focus::Scripts,
+        };
+    }
+
+    /// Moves the grid cursor by the given number of rows, clamped to the result set
+    pub fn move_result_row(&mut self, delta: isize) {
+        let row_count = self.current_result_set().map_or(0, |rs| rs.rows.len());
+        if row_count == 0 {
+            return;
+        }
+        let row = self.result_view.row.saturating_add_signed(delta);
+        self.result_view.row = row.min(row_count - 1);
+        self.load_more_browser_rows();
+    }
+
+    /// Moves the grid cursor by the given number of columns, clamped to the result set
+    pub fn move_result_column(&mut self, delta: isize) {
+        let column_count = self.current_result_set().map_or(0, |rs| rs.columns.len());
+        if column_count == 0 {
+            return;
+        }
+        let column = self.result_view.column.saturating_add_signed(delta);
+        self.result_view.column = column.min(column_count - 1);
+    }
+
+    pub fn result_page_down(&mut self) {
+        self.move_result_row(self.result_view.page_height.max(1) as isize);
+    }
+
+    pub fn result_page_up(&mut self) {
+        self.move_result_row(-(self.result_view.page_height.max(1) as isize));
+    }
+
+    pub fn first_result_row(&mut self) {
+        self.result_view.row = 0;
+    }
+
+    pub fn last_result_row(&mut self) {
+        let row_count = self.current_result_set().map_or(0, |rs| rs.rows.len());
+        self.result_view.row = row_count.saturating_sub(1);
+        // A browsed table jumps one page further each time, rather than loading every row
+        self.load_more_browser_rows();
+        let row_count = self.current_result_set().map_or(0, |rs| rs.rows.len());
+        self.result_view.row = row_count.saturating_sub(1);
+    }
+
     pub fn update_preview(&mut self) {
+        // Stay scrolled when the same script is reloaded, start at the top for a new one
+        let selected_path = self
+            .list_state
+            .selected()
+            .and_then(|i| self.sql_files.get(i))
+            .cloned();
+        if selected_path != self.preview_path {
+            self.preview_path = selected_path;
+            self.preview_scroll = 0;
+        }
         if let Some(selected_index) = self.list_state.selected() {
             if let Some(file_path) = self.sql_files.get(selected_index) {
+                if self.is_folder(file_path) {
+                    let count = self
+                        .all_sql_files
+                        .iter()
+                        .filter(|script| Path::new(script).starts_with(file_path))
+                        .count();
+                    let relative = Path::new(file_path)
+                        .strip_prefix(&self.script_dir_path)
+                        .unwrap_or(Path::new(file_path));
+                    self.script_content_preview = format!(
+                        "Folder {}/\n\n{} script(s) inside.",
+                        relative.display(),
+                        count
+                    );
+                    return;
+                }
                 self.script_content_preview = fs::read_to_string(file_path)
                     .unwrap_or_else(|e| format!("Error reading file {}: {}", file_path, e));
+                // Re-read on every selection so a directive edited outside the app shows up
+                match database_directive(&self.script_content_preview) {
+                    Some(name) => {
+                        self.script_databases
+                            .insert(file_path.clone(), name.to_string());
+                    }
+                    None => {
+                        self.script_databases.remove(file_path);
+                    }
+                }
             }
+        } else if !self.search_query.is_empty() {
+            self.script_content_preview = "No scripts match the search.".to_string();
         } else {
             self.script_content_preview = "No SQL files found.".to_string();
         }
     }
 }
+
+/// Measures the natural display width of every column, header included
+fn measure_columns(result_set: &ResultSet, value_format: &ValueFormat) -> Vec<usize> {
+    let mut widths: Vec<usize> = result_set
+        .columns
+        .iter()
+        .map(|name| name.chars().count())
+        .collect();
+    for row in &result_set.rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            let rendered = render_inline(value.into(), value_format);
+            *width = (*width).max(rendered.chars().count());
+        }
+    }
+    widths
+}
+
+/// The text of the help popup
+fn help_message(script_dir_path: &Path, database: &Database, keymap: &KeyMap) -> String {
+    format!(
+        "Welcome to sqledger!\n\nScripts: {}\nDatabase: {} ({})\n\n--- Keybinds ---\n{}\n\nRebind keys under [keys] in config.toml with the names in brackets,\ne.g. quit = [\"q\", \"Ctrl-q\"]. Other popups list their own keys on their borders.",
+        script_dir_path.display(),
+        database.name,
+        database.path.display(),
+        keymap.help_lines().join("\n")
+    )
+}
+
+/// Collects every folder and `.sql` file below `dir`, skipping hidden folders such as `.git`
+pub fn walk_script_dir(
+    dir: &Path,
+    folders: &mut Vec<PathBuf>,
+    scripts: &mut Vec<PathBuf>,
+) -> io::Result<()> {
+    for entry in fs::read_dir(dir)?.flatten() {
+        let path = entry.path();
+        if path.is_dir() {
+            if entry.file_name().to_string_lossy().starts_with('.') {
+                continue;
+            }
+            folders.push(path.clone());
+            walk_script_dir(&path, folders, scripts)?;
+        } else if path.extension().is_some_and(|ext| ext == "sql") {
+            scripts.push(path);
+        }
+    }
+    Ok(())
+}
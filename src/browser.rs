@@ -0,0 +1,242 @@
+use crate::{
+    db::{collect_rows, open_database, ResultSet},
+    output::quote_identifier,
+    params::parse_parameter_value,
+    schema::table_info,
+};
+use rusqlite::{types::Value, Connection, Result as SqlResult};
+use std::path::PathBuf;
+
+/// Rows fetched per page while browsing a table
+pub const BROWSE_PAGE_SIZE: usize = 200;
+
+/// Sort direction of the browsed column
+#[derive(Clone, Copy, PartialEq, Debug)]
+pub enum SortOrder {
+    Ascending,
+    Descending,
+}
+
+/// A table or view opened in the results grid, fetched a page at a time
+#[derive(Debug)]
+pub struct TableBrowser {
+    pub db_path: PathBuf,
+    pub read_only: bool,
+    pub table: String,
+    pub columns: Vec<String>,
+    pub sort: Option<(usize, SortOrder)>,
+    /// Filter expression per column, as typed; empty means unfiltered
+    pub filters: Vec<String>,
+    /// Set once a page comes back short, so no more fetches are made
+    pub exhausted: bool,
+}
+
+impl TableBrowser {
+    /// The query for the current sort and filters, with the values to bind, minus paging
+    pub fn query(&self) -> Result<(String, Vec<Value>), String> {
+        self.query_ordered_by(&[])
+    }
+
+    /// The query for the current sort and filters, then ordered by `tiebreaker`
+    fn query_ordered_by(&self, tiebreaker: &[String]) -> Result<(String, Vec<Value>), String> {
+        let mut sql = format!("SELECT * FROM {}", quote_identifier(&self.table));
+        let mut values = Vec::new();
+        let mut conditions = Vec::new();
+        for (column, filter) in self.columns.iter().zip(&self.filters) {
+            if filter.trim().is_empty() {
+                continue;
+            }
+            let (condition, value) = filter_condition(column, filter, values.len() + 1)?;
+            conditions.push(condition);
+            values.extend(value);
+        }
+        if !conditions.is_empty() {
+            sql.push_str(" WHERE ");
+            sql.push_str(&conditions.join(" AND "));
+        }
+        let mut order = Vec::new();
+        if let Some((column, sort_order)) = self.sort {
+            let direction = match sort_order {
+                SortOrder::Ascending => "ASC",
+                SortOrder::Descending => "DESC",
+            };
+            order.push(format!(
+                "{} {}",
+                quote_identifier(&self.columns[column]),
+                direction
+            ));
+        }
+        order.extend(tiebreaker.iter().cloned());
+        if !order.is_empty() {
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&order.join(", "));
+        }
+        Ok((sql, values))
+    }
+
+    /// Fetches the page of rows starting at `offset`.
+    ///
+    /// Pages are ordered by a unique key after the sort column, so rows sharing a sort value
+    /// come back in the same order on every page and none repeat or go missing.
+    pub fn fetch(&mut self, offset: usize) -> Result<ResultSet, String> {
+        let conn = open_database(&self.db_path, self.read_only).map_err(|e| e.to_string())?;
+        let tiebreaker = unique_order(&conn, &self.table).map_err(|e| e.to_string())?;
+        let (sql, mut values) = self.query_ordered_by(&tiebreaker)?;
+        let sql = format!(
+            "{} LIMIT ?{} OFFSET ?{}",
+            sql,
+            values.len() + 1,
+            values.len() + 2
+        );
+        values.push(Value::Integer(BROWSE_PAGE_SIZE as i64));
+        values.push(Value::Integer(offset as i64));
+        let page = (|| -> SqlResult<ResultSet> {
+            let mut stmt = conn.prepare(&sql)?;
+            for (i, value) in values.iter().enumerate() {
+                stmt.raw_bind_parameter(i + 1, value)?;
+            }
+            collect_rows(&mut stmt)
+        })()
+        .map_err(|e| e.to_string())?;
+        self.exhausted = page.rows.len() < BROWSE_PAGE_SIZE;
+        Ok(page)
+    }
+
+    /// Cycles the sort on `column`: ascending, then descending, then unsorted
+    pub fn cycle_sort(&mut self, column: usize) {
+        self.sort = match self.sort {
+            Some((sorted, SortOrder::Ascending)) if sorted == column => {
+                Some((column, SortOrder::Descending))
+            }
+            Some((sorted, SortOrder::Descending)) if sorted == column => None,
+            _ => Some((column, SortOrder::Ascending)),
+        };
+    }
+
+    /// One-line summary of the sort and filters, for the status line
+    pub fn describe(&self) -> String {
+        let mut parts = Vec::new();
+        if let Some((column, order)) = self.sort {
+            let direction = match order {
+                SortOrder::Ascending => "asc",
+                SortOrder::Descending => "desc",
+            };
+            parts.push(format!("sorted by {} {}", self.columns[column], direction));
+        }
+        let filters: Vec<String> = self
+            .columns
+            .iter()
+            .zip(&self.filters)
+            .filter(|(_, filter)| !filter.trim().is_empty())
+            .map(|(column, filter)| format!("{} {}", column, filter.trim()))
+            .collect();
+        if !filters.is_empty() {
+            parts.push(format!("where {}", filters.join(" and ")));
+        }
+        parts.join(", ")
+    }
+}
+
+/// Terms that order the rows of `table` completely: the rowid for ordinary tables, the primary
+/// key for `WITHOUT ROWID` tables, and every column for views, whose rows have no identity
+fn unique_order(conn: &Connection, table: &str) -> SqlResult<Vec<String>> {
+    let columns = table_info(conn, table)?;
+    let is_table = conn.query_row(
+        "SELECT count(*) > 0 FROM sqlite_schema WHERE type = 'table' AND name = ?1",
+        [table],
+        |row| row.get::<_, bool>(0),
+    )?;
+    // A column may shadow a rowid alias, and WITHOUT ROWID tables have none to select
+    let rowid = ["rowid", "_rowid_", "oid"].into_iter().find(|alias| {
+        !columns
+            .iter()
+            .any(|column| column.name.eq_ignore_ascii_case(alias))
+    });
+    if let Some(rowid) = rowid.filter(|_| is_table) {
+        let select = format!("SELECT {} FROM {}", rowid, quote_identifier(table));
+        if conn.prepare(&select).is_ok() {
+            return Ok(vec![rowid.to_string()]);
+        }
+    }
+    let mut key: Vec<_> = columns
+        .iter()
+        .filter(|column| column.primary_key > 0)
+        .collect();
+    key.sort_by_key(|column| column.primary_key);
+    if key.is_empty() {
+        key = columns.iter().collect();
+    }
+    Ok(key
+        .into_iter()
+        .map(|column| quote_identifier(&column.name))
+        .collect())
+}
+
+/// Turns a typed filter into an SQL condition on `column` using parameter `?index`.
+///
+/// A filter is an optional operator followed by a value: `=`, `!=`, `<`, `<=`, `>`, `>=`,
+/// `~` (LIKE) or `!~` (NOT LIKE). A bare value containing `%` means LIKE, otherwise `=`.
+/// `NULL` and `!NULL` test for (non-)NULL values.
+pub fn filter_condition(
+    column: &str,
+    filter: &str,
+    index: usize,
+) -> Result<(String, Option<Value>), String> {
+    let column = quote_identifier(column);
+    let filter = filter.trim();
+    match filter.to_uppercase().as_str() {
+        "NULL" => return Ok((format!("{} IS NULL", column), None)),
+        "!NULL" => return Ok((format!("{} IS NOT NULL", column), None)),
+        _ => {}
+    }
+    // Longest operators first so `<=` isn't read as `<` followed by `=...`
+    const OPERATORS: [(&str, &str); 8] = [
+        ("!=", "!="),
+        ("<=", "<="),
+        (">=", ">="),
+        ("!~", "NOT LIKE"),
+        ("=", "="),
+        ("<", "<"),
+        (">", ">"),
+        ("~", "LIKE"),
+    ];
+    let (operator, operand) = OPERATORS
+        .iter()
+        .find_map(|(prefix, operator)| Some((*operator, filter.strip_prefix(prefix)?.trim())))
+        .unwrap_or_else(|| {
+            if filter.contains('%') {
+                ("LIKE", filter)
+            } else {
+                ("=", filter)
+            }
+        });
+    if operand.is_empty() {
+        return Err(format!("Filter '{}' needs a value", filter));
+    }
+    // LIKE patterns are always text; comparisons use the same typing as script parameters
+    let value = if operator.ends_with("LIKE") {
+        Value::Text(operand.to_string())
+    } else {
+        parse_parameter_value(operand)
+    };
+    Ok((format!("{} {} ?{}", column, operator, index), Some(value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pages_are_ordered_by_a_unique_key() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch(
+            "CREATE TABLE plain(a, rowid);
+             CREATE TABLE keyed(a, b, c, PRIMARY KEY (c, b)) WITHOUT ROWID;
+             CREATE VIEW v AS SELECT a FROM plain;",
+        )
+        .unwrap();
+        assert_eq!(unique_order(&conn, "plain").unwrap(), ["_rowid_"]);
+        assert_eq!(unique_order(&conn, "keyed").unwrap(), ["\"c\"", "\"b\""]);
+        assert_eq!(unique_order(&conn, "v").unwrap(), ["\"a\""]);
+    }
+}
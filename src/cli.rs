@@ -0,0 +1,389 @@
+use crate::{
+    app::walk_script_dir,
+    config::Database,
+    db::{database_directive, find_parameters, open_database, run_script, StatementOutput},
+    history::{History, RunRecord},
+    output::{write_result_sets, OutputFormat},
+    params::{load_saved_parameters, parse_parameter_value},
+    value::ValueFormat,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    io::{self, IsTerminal, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command as ProcessCommand, ExitCode},
+    time::{Instant, SystemTime},
+};
+
+pub const USAGE: &str = "Usage:
+  plx [--db <name|path>] [--read-only] [command]
+
+Commands:
+  plx                          Start the interactive TUI
+  plx list                     List saved scripts, including those in subfolders
+  plx show <name>              Print a script's SQL
+  plx new <name>               Create a script (from stdin when piped, otherwise in $EDITOR)
+  plx run <name> [options]     Run a script and print its results
+
+Options for run:
+  -f, --format <format>        table (default), csv, tsv, json, ndjson, markdown or insert
+  -p, --param <name>=<value>   Bind a script parameter; may be repeated. Quote a value
+                               as '...' to bind it as text, e.g. --param id=\"'00123'\"
+
+JSON output is an array of row objects when the script returns one result set,
+and an array of such arrays otherwise ([] when it returns none).
+
+Global options:
+  --db <name|path>             Use a database from [databases] in config.toml, or a SQLite file
+  --read-only                  Open databases read-only, so scripts can't modify them";
+
+/// What the binary was asked to do
+#[derive(Debug)]
+pub enum Command {
+    Tui,
+    List,
+    Show {
+        name: String,
+    },
+    New {
+        name: String,
+    },
+    Run {
+        name: String,
+        format: OutputFormat,
+        params: Vec<(String, String)>,
+    },
+    Help,
+}
+
+/// Options that apply to every command, accepted anywhere on the command line
+#[derive(Debug, Default)]
+pub struct GlobalOptions {
+    /// `--db`: a configured database name or a SQLite file path
+    pub database: Option<String>,
+    /// `--read-only`
+    pub read_only: bool,
+}
+
+/// Paths and settings resolved by `main` that the subcommands share with the TUI
+pub struct Context<'a> {
+    pub script_dir_path: &'a Path,
+    pub db_path: &'a Path,
+    /// Every configured database, for scripts bound to one with `-- @db:`
+    pub databases: &'a [Database],
+    pub parameters_path: &'a Path,
+    pub history_path: &'a Path,
+    pub value_format: ValueFormat,
+    pub read_only: bool,
+}
+
+/// Parses the command line, not including the program name.
+/// Global options may appear anywhere and are returned alongside the command.
+pub fn parse_args<I: Iterator<Item = String>>(
+    mut args: I,
+) -> Result<(Command, GlobalOptions), String> {
+    let mut options = GlobalOptions::default();
+    let mut rest = Vec::new();
+    while let Some(arg) = args.next() {
+        if arg == "--db" {
+            options.database = Some(args.next().ok_or("Missing value for --db")?);
+        } else if let Some(value) = arg.strip_prefix("--db=") {
+            options.database = Some(value.to_string());
+        } else if arg == "--read-only" {
+            options.read_only = true;
+        } else {
+            rest.push(arg);
+        }
+    }
+    Ok((parse_command(rest.into_iter())?, options))
+}
+
+fn parse_command<I: Iterator<Item = String>>(mut args: I) -> Result<Command, String> {
+    let Some(subcommand) = args.next() else {
+        return Ok(Command::Tui);
+    };
+    match subcommand.as_str() {
+        "list" => Ok(Command::List),
+        "show" => Ok(Command::Show {
+            name: required_name(&mut args, "show")?,
+        }),
+        "new" => Ok(Command::New {
+            name: required_name(&mut args, "new")?,
+        }),
+        "run" => {
+            let name = required_name(&mut args, "run")?;
+            let mut format = OutputFormat::Table;
+            let mut params = Vec::new();
+            while let Some(arg) = args.next() {
+                match arg.as_str() {
+                    "-f" | "--format" => {
+                        let value = args.next().ok_or("Missing value for --format")?;
+                        format = OutputFormat::parse(&value)
+                            .ok_or_else(|| format!("Unknown output format '{}'", value))?;
+                    }
+                    "-p" | "--param" => {
+                        let value = args.next().ok_or("Missing value for --param")?;
+                        let (param_name, param_value) = value
+                            .split_once('=')
+                            .ok_or_else(|| format!("Expected NAME=VALUE, got '{}'", value))?;
+                        params.push((param_name.to_string(), param_value.to_string()));
+                    }
+                    other => return Err(format!("Unexpected argument '{}'", other)),
+                }
+            }
+            Ok(Command::Run {
+                name,
+                format,
+                params,
+            })
+        }
+        "help" | "-h" | "--help" => Ok(Command::Help),
+        other => Err(format!("Unknown command '{}'", other)),
+    }
+}
+
+fn required_name<I: Iterator<Item = String>>(
+    args: &mut I,
+    subcommand: &str,
+) -> Result<String, String> {
+    args.next()
+        .ok_or_else(|| format!("'{}' needs a script name", subcommand))
+}
+
+/// Runs a non-interactive subcommand and returns the process exit code
+pub fn run_command(command: Command, context: &Context) -> io::Result<ExitCode> {
+    match command {
+        Command::Tui => unreachable!("the TUI is started by main"),
+        Command::Help => {
+            println!("{}", USAGE);
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::List => {
+            let mut scripts = Vec::new();
+            walk_script_dir(context.script_dir_path, &mut Vec::new(), &mut scripts)?;
+            // Names relative to the script directory, which is what `show` and `run` accept
+            let mut names: Vec<String> = scripts
+                .iter()
+                .filter_map(|path| path.strip_prefix(context.script_dir_path).ok())
+                .map(|path| path.with_extension("").to_string_lossy().to_string())
+                .collect();
+            names.sort();
+            for name in names {
+                println!("{}", name);
+            }
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Show { name } => {
+            let script_path = script_path(context, &name);
+            match fs::read_to_string(&script_path) {
+                Ok(sql_content) => {
+                    print!("{}", sql_content);
+                    Ok(ExitCode::SUCCESS)
+                }
+                Err(e) => {
+                    eprintln!("Error reading file {}: {}", script_path.display(), e);
+                    Ok(ExitCode::FAILURE)
+                }
+            }
+        }
+        Command::New { name } => {
+            let script_path = script_path(context, &name);
+            if script_path.exists() {
+                eprintln!("Error: File {} already exists.", script_path.display());
+                return Ok(ExitCode::FAILURE);
+            }
+            if io::stdin().is_terminal() {
+                fs::write(&script_path, "")?;
+                let editor = std::env::var("EDITOR").unwrap_or_else(|_| "nvim".to_string());
+                let status = ProcessCommand::new(editor).arg(&script_path).status()?;
+                if !status.success() {
+                    eprintln!("Editor exited with an error.");
+                    return Ok(ExitCode::FAILURE);
+                }
+            } else {
+                let mut sql_content = String::new();
+                io::stdin().read_to_string(&mut sql_content)?;
+                fs::write(&script_path, sql_content)?;
+            }
+            eprintln!("Script {} created successfully.", script_path.display());
+            Ok(ExitCode::SUCCESS)
+        }
+        Command::Run {
+            name,
+            format,
+            params,
+        } => run(context, &name, format, &params, &mut io::stdout().lock()),
+    }
+}
+
+fn run<W: Write>(
+    context: &Context,
+    name: &str,
+    format: OutputFormat,
+    params: &[(String, String)],
+    out: &mut W,
+) -> io::Result<ExitCode> {
+    let script_path = script_path(context, name);
+    let sql_content = match fs::read_to_string(&script_path) {
+        Ok(sql_content) => sql_content,
+        Err(e) => {
+            eprintln!("Error reading file {}: {}", script_path.display(), e);
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+    let db_path = match database_directive(&sql_content) {
+        Some(bound) => match context.databases.iter().find(|db| db.name == bound) {
+            Some(database) => database.path.as_path(),
+            None => {
+                eprintln!(
+                    "{} is bound to database '{}', which is not configured.",
+                    name, bound
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+        },
+        None => context.db_path,
+    };
+    let conn = match open_database(db_path, context.read_only) {
+        Ok(conn) => conn,
+        Err(e) => {
+            eprintln!("Error opening database {}: {}", db_path.display(), e);
+            return Ok(ExitCode::FAILURE);
+        }
+    };
+
+    // Values given on the command line win; otherwise fall back to the last values used in the TUI
+    let saved = load_saved_parameters(context.parameters_path);
+    let remembered = saved.get(&script_path.to_string_lossy().to_string());
+    let mut values = HashMap::new();
+    for param_name in find_parameters(&conn, &sql_content) {
+        let given = params
+            .iter()
+            .find(|(given_name, _)| parameter_matches(given_name, &param_name))
+            .map(|(_, value)| value);
+        match given.or_else(|| remembered.and_then(|values| values.get(&param_name))) {
+            Some(value) => {
+                values.insert(param_name, parse_parameter_value(value));
+            }
+            None => {
+                eprintln!(
+                    "Missing value for parameter {}; pass it with --param {}=<value>",
+                    param_name,
+                    param_name.trim_start_matches([':', '@', '$'])
+                );
+                return Ok(ExitCode::FAILURE);
+            }
+        }
+    }
+
+    let started_at = SystemTime::now();
+    let timer = Instant::now();
+    let mut results = Vec::new();
+    let outcome = run_script(&conn, &sql_content, &values, &mut results);
+    let duration = timer.elapsed();
+
+    let script_path_str = script_path.to_string_lossy();
+    let record = RunRecord::from_results(
+        &script_path_str,
+        &sql_content,
+        started_at,
+        duration,
+        &results,
+        outcome.as_ref().err().map(|e| e.to_string()),
+    );
+    if let Err(e) = History::open(context.history_path).and_then(|h| h.record(&record)) {
+        eprintln!("Warning: could not record run in history: {}", e);
+    }
+
+    let mut result_sets = Vec::new();
+    for result in &results {
+        match &result.output {
+            StatementOutput::Rows(result_set) => result_sets.push(result_set),
+            StatementOutput::Changes(changes) => eprintln!("{} rows affected.", changes),
+            StatementOutput::Done | StatementOutput::Plan(_) => {}
+        }
+    }
+    write_result_sets(out, &result_sets, format, name, &context.value_format)?;
+    out.flush()?;
+
+    match outcome {
+        Ok(()) => Ok(ExitCode::SUCCESS),
+        Err(e) => {
+            eprintln!("Error executing statement {}: {}", results.len() + 1, e);
+            Ok(ExitCode::FAILURE)
+        }
+    }
+}
+
+/// Resolves a script name to its file, accepting either a bare name or a path to a .sql file
+fn script_path(context: &Context, name: &str) -> PathBuf {
+    let path = Path::new(name);
+    if path.extension().is_some_and(|ext| ext == "sql") && path.exists() {
+        path.to_path_buf()
+    } else {
+        context.script_dir_path.join(format!("{}.sql", name))
+    }
+}
+
+/// Lets `--param min=1` match `:min`, `@min` or `$min`, as well as the exact spelling
+fn parameter_matches(given_name: &str, param_name: &str) -> bool {
+    given_name == param_name || param_name[1..] == *given_name
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Runs `sql` as a script in a scratch directory and returns what it printed
+    fn run_script_output(test: &str, sql: &str, format: OutputFormat) -> String {
+        let dir = std::env::temp_dir().join(format!("plx-cli-{}-{}", test, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        fs::write(dir.join("script.sql"), sql).unwrap();
+        let db_path = dir.join("test.db");
+        rusqlite::Connection::open(&db_path).unwrap();
+        let context = Context {
+            script_dir_path: &dir,
+            db_path: &db_path,
+            databases: &[],
+            parameters_path: &dir.join("parameters.json"),
+            history_path: &dir.join("history.db"),
+            value_format: ValueFormat::default(),
+            read_only: false,
+        };
+        let mut out = Vec::new();
+        run(&context, "script", format, &[], &mut out).unwrap();
+        fs::remove_dir_all(&dir).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn two_selects_stay_valid_json() {
+        let output = run_script_output(
+            "json",
+            "SELECT 1 AS a; SELECT 'x' AS b;",
+            OutputFormat::Json,
+        );
+        let json: serde_json::Value = serde_json::from_str(&output).unwrap();
+        assert_eq!(json, serde_json::json!([[{ "a": 1 }], [{ "b": "x" }]]));
+    }
+
+    #[test]
+    fn no_result_sets_is_an_empty_json_array() {
+        let output = run_script_output("empty", "CREATE TABLE t(a);", OutputFormat::Json);
+        assert_eq!(output.trim(), "[]");
+    }
+
+    #[test]
+    fn two_selects_are_separated_by_a_blank_line() {
+        let output = run_script_output("csv", "SELECT 1 AS a; SELECT 2 AS b;", OutputFormat::Csv);
+        assert_eq!(output, "a\r\n1\r\n\nb\r\n2\r\n");
+        let output = run_script_output(
+            "markdown",
+            "SELECT 1 AS a; SELECT 2 AS b;",
+            OutputFormat::Markdown,
+        );
+        assert!(output.contains("| 1 |\n\n| b |"), "{}", output);
+    }
+}
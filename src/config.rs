@@ -1,15 +1,98 @@
+use crate::{keys::KeyList, theme::ThemeConfig};
 use serde::Deserialize;
-use std::{fs, path::Path};
+use std::{
+    collections::BTreeMap,
+    fs,
+    path::{Path, PathBuf},
+};
 
 pub const DB_NAME: &str = "scripts.db";
 pub const CONFIG_DIR_NAME: &str = "sqledger";
 pub const CONFIG_FILE_NAME: &str = "config.toml";
+pub const PARAMETERS_FILE_NAME: &str = "parameters.json";
+pub const HISTORY_DB_NAME: &str = "history.db";
 pub const DEFAULT_SCRIPTS_DIR: &str = "~/.config/sqledger/scripts";
+/// Name of the built-in database used when no `[databases]` are configured
+pub const DEFAULT_DATABASE_NAME: &str = "default";
 
 #[derive(Deserialize, Debug)]
 pub struct Config {
     #[serde(default = "default_script_dir")]
     pub script_directory: String,
+    /// Decimal places shown for REAL values; unset shows the shortest exact form
+    #[serde(default)]
+    pub float_precision: Option<usize>,
+    /// Database the TUI starts on; a name from `databases`
+    #[serde(default)]
+    pub default_database: Option<String>,
+    /// Named SQLite files scripts can be run against, e.g. `staging = "~/db/staging.db"`
+    #[serde(default)]
+    pub databases: BTreeMap<String, String>,
+    /// Open every database read-only, as if `--read-only` were always given
+    #[serde(default)]
+    pub read_only: bool,
+    /// Keys per action, e.g. `quit = ["q", "Ctrl-q"]`, replacing that action's defaults
+    #[serde(default)]
+    pub keys: BTreeMap<String, KeyList>,
+    /// A colour preset and per-role overrides, e.g. `selection = "white on blue"`
+    #[serde(default)]
+    pub theme: ThemeConfig,
+    /// Pane proportions in percent
+    #[serde(default)]
+    pub layout: PaneLayout,
+}
+
+/// The `[layout]` section: how the screen is split between the panes, in percent
+#[derive(Deserialize, Debug, Clone, Copy)]
+#[serde(default)]
+pub struct PaneLayout {
+    /// Width of the script list and schema browser; the preview and results get the rest
+    pub list_width: u16,
+    /// Height of the preview above the results
+    pub preview_height: u16,
+}
+
+impl Default for PaneLayout {
+    fn default() -> Self {
+        Self {
+            list_width: 20,
+            preview_height: 40,
+        }
+    }
+}
+
+impl PaneLayout {
+    const RANGE: std::ops::RangeInclusive<u16> = 10..=90;
+
+    /// Falls back to the default for any ratio outside 10-90%, reporting it
+    pub fn checked(self) -> (Self, Vec<String>) {
+        let defaults = Self::default();
+        let mut problems = Vec::new();
+        let mut check = |name: &str, value: u16, default: u16| {
+            if Self::RANGE.contains(&value) {
+                value
+            } else {
+                problems.push(format!("[layout] {} = {} is not within 10-90", name, value));
+                default
+            }
+        };
+        let layout = Self {
+            list_width: check("list_width", self.list_width, defaults.list_width),
+            preview_height: check(
+                "preview_height",
+                self.preview_height,
+                defaults.preview_height,
+            ),
+        };
+        (layout, problems)
+    }
+}
+
+/// A named SQLite file scripts can be run against
+#[derive(Clone, Debug)]
+pub struct Database {
+    pub name: String,
+    pub path: PathBuf,
 }
 
 fn default_script_dir() -> String {
@@ -20,6 +103,13 @@ impl Default for Config {
     fn default() -> Self {
         Self {
             script_directory: default_script_dir(),
+            float_precision: None,
+            default_database: None,
+            databases: BTreeMap::new(),
+            read_only: false,
+            keys: BTreeMap::new(),
+            theme: ThemeConfig::default(),
+            layout: PaneLayout::default(),
         }
     }
 }
@@ -36,3 +126,64 @@ pub fn load_config(config_path: &Path) -> Config {
     }
     Config::default()
 }
+
+/// The configured databases in name order, or the built-in one under `data_dir` if none are set
+pub fn configured_databases(config: &Config, data_dir_path: &Path) -> Vec<Database> {
+    if config.databases.is_empty() {
+        return vec![Database {
+            name: DEFAULT_DATABASE_NAME.to_string(),
+            path: data_dir_path.join(DB_NAME),
+        }];
+    }
+    config
+        .databases
+        .iter()
+        .map(|(name, path)| Database {
+            name: name.clone(),
+            path: PathBuf::from(shellexpand::tilde(path).to_string()),
+        })
+        .collect()
+}
+
+/// Picks the database to start on: `requested` (a `--db` name or path) first, then
+/// `default_database`, then the first configured one.
+/// A requested path that is not a configured database is added as an ad-hoc entry.
+pub fn choose_database(
+    databases: &mut Vec<Database>,
+    requested: Option<&str>,
+    default_database: Option<&str>,
+) -> Result<usize, String> {
+    if let Some(requested) = requested {
+        if let Some(index) = databases.iter().position(|db| db.name == requested) {
+            return Ok(index);
+        }
+        let path = PathBuf::from(shellexpand::tilde(requested).to_string());
+        if !path.is_file() {
+            return Err(format!(
+                "'{}' is neither a configured database nor an existing file",
+                requested
+            ));
+        }
+        if let Some(index) = databases.iter().position(|db| db.path == path) {
+            return Ok(index);
+        }
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_else(|| requested.to_string());
+        databases.push(Database { name, path });
+        return Ok(databases.len() - 1);
+    }
+    if let Some(default_database) = default_database {
+        return databases
+            .iter()
+            .position(|db| db.name == default_database)
+            .ok_or_else(|| {
+                format!(
+                    "default_database '{}' is not in [databases]",
+                    default_database
+                )
+            });
+    }
+    Ok(0)
+}
@@ -1,86 +1,884 @@
-use crate::app::App; // ‼️ Use crate-relative path
-use rusqlite::{Connection, Error as RusqliteError};
-use std::fs;
-
-pub fn execute_sql(app: &mut App, db_path: &str) {
-    if let Some(selected_index) = app.list_state.selected() {
-        let file_path = &app.sql_files[selected_index];
-        match fs::read_to_string(file_path) {
-            Ok(sql_content) => {
-                let conn = match Connection::open(db_path) {
-                    Ok(conn) => conn,
-                    Err(e) => {
-                        app.query_result = format!("Error opening database {}: {}", db_path, e);
-                        return;
-                    }
-                };
-
-                let trimmed_sql = sql_content.trim();
-                if trimmed_sql.to_uppercase().starts_with("SELECT")
-                    || trimmed_sql.to_uppercase().starts_with("PRAGMA")
-                {
-                    match (|| -> Result<String, RusqliteError> {
-                        let mut stmt = conn.prepare(&sql_content)?;
-                        let column_names: Vec<String> =
-                            stmt.column_names().iter().map(|s| s.to_string()).collect();
-                        let mut widths: Vec<usize> = column_names.iter().map(|s| s.len()).collect();
-                        let mut rows_data: Vec<Vec<String>> = Vec::new();
-
-                        let rows = stmt.query_map([], |row| {
-                            let mut values = Vec::new();
-                            for (i, width) in widths.iter_mut().enumerate() {
-                                let val: String = row.get(i).unwrap_or_else(|_| "NULL".to_string());
-                                *width = (*width).max(val.len());
-                                values.push(val);
-                            }
-                            Ok(values)
-                        })?;
-
-                        for row_result in rows {
-                            rows_data.push(row_result?);
-                        }
+use crate::{
+    app::{App, InputMode, LeftPane},
+    config::Database,
+    history::RunRecord,
+    keys::Action,
+    lexer::{split_statements, tokenize, TokenKind},
+    params::number_parameters,
+    plan::{explain_statement, QueryPlan},
+}; // ‼️ Use crate-relative path
+use rusqlite::{
+    fallible_iterator::FallibleIterator, types::Value, Batch, Connection, Error as RusqliteError,
+    InterruptHandle, OpenFlags, Statement, StatementStatus,
+};
+use std::{
+    collections::HashMap,
+    fs,
+    path::Path,
+    sync::mpsc::{self, Receiver, TryRecvError},
+    thread,
+    time::{Duration, Instant, SystemTime},
+};
 
-                        let mut output = String::new();
-                        for (i, name) in column_names.iter().enumerate() {
-                            output.push_str(&format!("{:<width$} | ", name, width = widths[i]));
-                        }
-                        output.push('\n');
-                        for width in &widths {
-                            output.push_str(&"-".repeat(*width));
-                            output.push_str("---");
-                        }
-                        output.push('\n');
-                        for row in rows_data {
-                            for (i, value) in row.iter().enumerate() {
-                                output.push_str(&format!(
-                                    "{:<width$} | ",
-                                    value,
-                                    width = widths[i]
-                                ));
-                            }
-                            output.push('\n');
-                        }
-                        Ok(output)
-                    })() {
-                        Ok(formatted_result) => app.query_result = formatted_result,
-                        Err(e) => app.query_result = format!("Error executing query: {}", e),
+/// The columns and rows produced by a single row-returning statement
+#[derive(Clone, Debug, Default)]
+pub struct ResultSet {
+    pub columns: Vec<String>,
+    pub rows: Vec<Vec<Value>>,
+}
+
+/// How a prepared statement has to be driven, decided from SQLite's own metadata
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum StatementKind {
+    /// Produces rows: SELECT, WITH, VALUES, EXPLAIN, most PRAGMAs, `... RETURNING`
+    Query,
+    /// Produces no rows and does not write to the database (BEGIN, COMMIT, ATTACH, ...)
+    ReadOnly,
+    /// Produces no rows but may write to the database
+    Modification,
+}
+
+impl StatementKind {
+    pub fn classify(stmt: &Statement) -> Self {
+        if stmt.column_count() > 0 {
+            StatementKind::Query
+        } else if stmt.readonly() {
+            StatementKind::ReadOnly
+        } else {
+            StatementKind::Modification
+        }
+    }
+}
+
+/// What a single statement in a script produced
+#[derive(Clone, Debug)]
+pub enum StatementOutput {
+    Rows(ResultSet),
+    Changes(usize),
+    Done,
+    /// How the statement would run, from explaining it instead of running it
+    Plan(QueryPlan),
+}
+
+/// The outcome of one statement, together with the SQL text that produced it
+#[derive(Clone, Debug)]
+pub struct StatementResult {
+    pub sql: String,
+    pub output: StatementOutput,
+    /// How running the statement went; `None` for results that didn't come from a run
+    pub stats: Option<StatementStats>,
+}
+
+/// Measurements taken while a statement ran
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StatementStats {
+    pub duration: Duration,
+    /// Rows inserted, updated or deleted by this statement alone
+    pub changes: usize,
+    pub rows_returned: usize,
+    /// Rows stepped through in full table scans (`SQLITE_STMTSTATUS_FULLSCAN_STEP`)
+    pub fullscan_steps: i32,
+    /// Sorts done without an index (`SQLITE_STMTSTATUS_SORT`)
+    pub sorts: i32,
+    /// Rows inserted into automatic indexes (`SQLITE_STMTSTATUS_AUTOINDEX`)
+    pub autoindex_rows: i32,
+    /// Virtual machine operations run (`SQLITE_STMTSTATUS_VM_STEP`)
+    pub vm_steps: i32,
+}
+
+/// A script run in progress on a worker thread
+pub struct RunningQuery {
+    pub script_path: String,
+    pub started: Instant,
+    pub cancelled: bool,
+    /// Whether the script runs on the open transaction's connection
+    pub in_transaction: bool,
+    /// Extra text appended to the status line once the run finishes
+    pub note: Option<String>,
+    interrupt: InterruptHandle,
+    receiver: Receiver<RunOutcome>,
+}
+
+impl RunningQuery {
+    /// Asks SQLite to abort the running statement; the worker then reports an interrupt error
+    pub fn cancel(&mut self) {
+        self.interrupt.interrupt();
+        self.cancelled = true;
+    }
+}
+
+/// What a worker thread sends back when a run ends
+pub struct RunOutcome {
+    pub sql: String,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    pub results: Vec<StatementResult>,
+    pub error: Option<String>,
+    /// Handed back so a transaction can stay open after the run
+    pub conn: Option<Connection>,
+}
+
+/// A script held back until the user confirms the modifications it will make
+pub struct PendingRun {
+    pub script_path: String,
+    pub modifications: Modifications,
+    sql: String,
+    conn: Option<Connection>,
+    parameters: HashMap<String, Value>,
+    note: Option<String>,
+}
+
+/// What a script may write, found by preparing its statements before running it
+#[derive(Debug, Default)]
+pub struct Modifications {
+    /// Statements that may write to the database, as they will run
+    pub statements: Vec<String>,
+    /// Why the rest of the script couldn't be checked, if a statement couldn't be prepared
+    pub unchecked: Option<String>,
+    /// Statements that would commit or roll back the open transaction the script runs in
+    pub ends_transaction: Vec<String>,
+}
+
+impl Modifications {
+    pub fn is_empty(&self) -> bool {
+        self.statements.is_empty() && self.unchecked.is_none() && self.ends_transaction.is_empty()
+    }
+}
+
+/// A transaction left open on its own connection until the user commits or rolls it back
+pub struct OpenTransaction {
+    pub database: Database,
+    pub started: Instant,
+    /// Scripts run inside the transaction so far
+    pub scripts: Vec<String>,
+    /// `None` while a run inside the transaction has the connection on the worker thread
+    conn: Option<Connection>,
+}
+
+/// The selected script, read and ready to run
+struct OpenedScript {
+    path: String,
+    sql: String,
+    database: Database,
+    /// A fresh connection, or `None` when the script runs on the open transaction's
+    conn: Option<Connection>,
+}
+
+impl OpenedScript {
+    fn connection<'a>(&'a self, app: &'a App) -> Option<&'a Connection> {
+        self.conn
+            .as_ref()
+            .or_else(|| app.transaction.as_ref()?.conn.as_ref())
+    }
+}
+
+/// Runs the selected script, first opening the parameter form if it uses bound parameters
+pub fn execute_sql(app: &mut App) {
+    if app.running.is_some() {
+        app.query_result = "A script is already running.".to_string();
+        return;
+    }
+    let Some(script) = open_selected_script(app) else {
+        return;
+    };
+    let names = script
+        .connection(app)
+        .map(|conn| find_parameters(conn, &script.sql))
+        .unwrap_or_default();
+    if names.is_empty() {
+        confirm_run(app, script, HashMap::new(), None);
+    } else {
+        app.open_parameter_form(script.path, names);
+    }
+}
+
+/// Runs the selected script with the values filled in on the parameter form
+pub fn execute_sql_with_parameters(app: &mut App) {
+    let parameters = app.parameter_form.values();
+    let save_error = app.remember_parameters().err();
+    let Some(script) = open_selected_script(app) else {
+        return;
+    };
+    let note = save_error.map(|e| format!("Could not save parameter values: {}", e));
+    confirm_run(app, script, parameters, note);
+}
+
+/// Begins a transaction on the selected script's database and runs the script inside it.
+/// The transaction stays open afterwards so its effects can be reviewed before committing.
+pub fn execute_sql_in_transaction(app: &mut App) {
+    if app.running.is_some() {
+        app.query_result = "A script is already running.".to_string();
+        return;
+    }
+    if app.transaction.is_some() {
+        app.input_mode = InputMode::ReviewingTransaction;
+        return;
+    }
+    let Some(OpenedScript {
+        database,
+        conn: Some(conn),
+        ..
+    }) = open_selected_script(app)
+    else {
+        return;
+    };
+    if let Err(e) = conn.execute_batch("BEGIN") {
+        app.report_failure(format!("Could not begin a transaction: {}", e));
+        return;
+    }
+    app.transaction = Some(OpenTransaction {
+        database,
+        started: Instant::now(),
+        scripts: Vec::new(),
+        conn: Some(conn),
+    });
+    execute_sql(app);
+}
+
+/// Shows how each statement of the selected script would run, without running any of them.
+/// A statement that can't be explained, e.g. because it uses a table an earlier one creates,
+/// shows its error and the rest are still explained.
+pub fn explain_sql(app: &mut App) {
+    if app.running.is_some() {
+        app.query_result = "A script is already running.".to_string();
+        return;
+    }
+    let Some(script) = open_selected_script(app) else {
+        return;
+    };
+    let Some(conn) = script.connection(app) else {
+        return;
+    };
+    let mut results = Vec::new();
+    let mut failures = Vec::new();
+    for (i, statement) in split_statements(&script.sql).into_iter().enumerate() {
+        match explain_statement(conn, statement) {
+            Ok(plan) => results.push(StatementResult {
+                sql: statement.to_string(),
+                output: StatementOutput::Plan(plan),
+                stats: None,
+            }),
+            Err(e) => failures.push(format!("statement {}: {}", i + 1, e)),
+        }
+    }
+    let full_scans: usize = results
+        .iter()
+        .map(|result| match &result.output {
+            StatementOutput::Plan(plan) => plan.full_scans(),
+            _ => 0,
+        })
+        .sum();
+    app.query_result = format!(
+        "Explained {} statement(s) without running them; {} full table scan(s). [{}] toggles bytecode.",
+        results.len(),
+        full_scans,
+        app.keymap.describe(Action::ToggleBytecode)
+    );
+    if !failures.is_empty() {
+        app.query_result
+            .push_str(&format!(" Could not explain {}.", failures.join("; ")));
+    }
+    app.show_bytecode = false;
+    app.set_results(results);
+}
+
+/// Commits or rolls back the open transaction. A failed COMMIT, e.g. because the database
+/// is locked, leaves the transaction open so it can be retried or rolled back.
+pub fn end_transaction(app: &mut App, commit: bool) {
+    app.input_mode = InputMode::Normal;
+    let Some(mut transaction) = app.transaction.take() else {
+        return;
+    };
+    let Some(conn) = transaction.conn.take() else {
+        app.query_result = "Wait for the running script to finish first.".to_string();
+        app.transaction = Some(transaction);
+        return;
+    };
+    let (statement, done) = if commit {
+        ("COMMIT", "Committed")
+    } else {
+        ("ROLLBACK", "Rolled back")
+    };
+    match conn.execute_batch(statement) {
+        Ok(()) => {
+            app.query_result = format!(
+                "{} the transaction on '{}' ({} script run(s)).",
+                done,
+                transaction.database.name,
+                transaction.scripts.len()
+            );
+            // Objects created in the transaction only become visible to other connections now
+            if commit && app.left_pane == LeftPane::Schema {
+                app.reload_schema();
+            }
+        }
+        Err(e) => {
+            app.report_failure(format!("{} failed: {}", statement, e));
+            transaction.conn = Some(conn);
+            app.transaction = Some(transaction);
+        }
+    }
+}
+
+/// Starts the run, unless the script writes to the database: then the confirmation popup
+/// lists the statements that would, and nothing runs until the user accepts.
+/// In read-only mode the connection itself refuses writes, and inside a transaction the
+/// writes can still be rolled back, so neither asks; unless the script would itself commit
+/// or roll back the transaction, taking that choice away from the review.
+fn confirm_run(
+    app: &mut App,
+    script: OpenedScript,
+    parameters: HashMap<String, Value>,
+    note: Option<String>,
+) {
+    let modifications = match &script.conn {
+        Some(conn) if !app.read_only => find_modifications(conn, &script.sql, &parameters),
+        Some(_) => Modifications::default(),
+        None => Modifications {
+            ends_transaction: find_transaction_ends(&script.sql),
+            ..Modifications::default()
+        },
+    };
+    if modifications.is_empty() {
+        start_run(app, script.path, script.sql, script.conn, parameters, note);
+        return;
+    }
+    let found = match (modifications.statements.len(), &modifications.unchecked) {
+        _ if !modifications.ends_transaction.is_empty() => format!(
+            "{} statement(s) would end the open transaction",
+            modifications.ends_transaction.len()
+        ),
+        (0, _) => "The script can't be fully checked for modifications".to_string(),
+        (count, None) => format!("{} statement(s) may modify the database", count),
+        (count, Some(_)) => format!(
+            "{} statement(s) may modify the database and the rest can't be checked",
+            count
+        ),
+    };
+    app.query_result = format!(
+        "{}. Run anyway? ([{}] run, [{}] cancel)",
+        found,
+        app.keymap.describe(Action::Confirm),
+        app.keymap.describe(Action::Cancel)
+    );
+    app.pending_run = Some(PendingRun {
+        script_path: script.path,
+        modifications,
+        sql: script.sql,
+        conn: script.conn,
+        parameters,
+        note,
+    });
+    app.input_mode = InputMode::ConfirmingRun;
+}
+
+/// Runs the script the user just confirmed
+pub fn run_confirmed(app: &mut App) {
+    if let Some(pending) = app.pending_run.take() {
+        start_run(
+            app,
+            pending.script_path,
+            pending.sql,
+            pending.conn,
+            pending.parameters,
+            pending.note,
+        );
+    }
+}
+
+/// Reads the selected script and opens its database, reporting failures in the status line
+fn open_selected_script(app: &mut App) -> Option<OpenedScript> {
+    let file_path = app.selected_script()?.clone();
+    let sql_content = match fs::read_to_string(&file_path) {
+        Ok(sql_content) => sql_content,
+        Err(e) => {
+            app.query_result = format!("Error reading file {}: {}", file_path, e);
+            return None;
+        }
+    };
+    let database = match database_directive(&sql_content) {
+        Some(name) => match app.databases.iter().find(|db| db.name == name) {
+            Some(database) => database.clone(),
+            None => {
+                app.query_result = format!(
+                    "This script is bound to database '{}', which is not configured.",
+                    name
+                );
+                return None;
+            }
+        },
+        None => app.active_database().clone(),
+    };
+    if let Some(transaction) = &app.transaction {
+        if transaction.database.path != database.path {
+            app.query_result = format!(
+                "A transaction is open on '{}'; commit or roll it back before running against '{}'.",
+                transaction.database.name, database.name
+            );
+            return None;
+        }
+        return Some(OpenedScript {
+            path: file_path,
+            sql: sql_content,
+            database,
+            conn: None,
+        });
+    }
+    match open_database(&database.path, app.read_only) {
+        Ok(conn) => Some(OpenedScript {
+            path: file_path,
+            sql: sql_content,
+            database,
+            conn: Some(conn),
+        }),
+        Err(e) => {
+            app.report_failure(format!(
+                "Error opening database {}: {}",
+                database.path.display(),
+                e
+            ));
+            None
+        }
+    }
+}
+
+/// The database a script is bound to by a `-- @db: <name>` line in its leading comments
+pub fn database_directive(sql: &str) -> Option<&str> {
+    for line in sql.lines().map(str::trim) {
+        if line.is_empty() {
+            continue;
+        }
+        let comment = line.strip_prefix("--")?.trim();
+        if let Some(name) = comment.strip_prefix("@db:") {
+            return Some(name.trim()).filter(|name| !name.is_empty());
+        }
+    }
+    None
+}
+
+/// Opens an existing database file; unlike `Connection::open`, a mistyped path is an error
+/// rather than a new empty database. A read-only connection makes SQLite reject every write.
+pub fn open_database(path: &Path, read_only: bool) -> Result<Connection, RusqliteError> {
+    let mut flags = OpenFlags::default() - OpenFlags::SQLITE_OPEN_CREATE;
+    if read_only {
+        flags = (flags - OpenFlags::SQLITE_OPEN_READ_WRITE) | OpenFlags::SQLITE_OPEN_READ_ONLY;
+    }
+    Connection::open_with_flags(path, flags)
+}
+
+/// Hands the script to a worker thread so the UI keeps drawing while it runs.
+/// Without a connection of its own, the script runs on the open transaction's.
+fn start_run(
+    app: &mut App,
+    script_path: String,
+    sql_content: String,
+    conn: Option<Connection>,
+    parameters: HashMap<String, Value>,
+    note: Option<String>,
+) {
+    let in_transaction = conn.is_none();
+    let conn = match conn {
+        Some(conn) => conn,
+        None => match app.transaction.as_mut().and_then(|t| t.conn.take()) {
+            Some(conn) => conn,
+            None => {
+                app.query_result = "The transaction is no longer open.".to_string();
+                return;
+            }
+        },
+    };
+    let (sender, receiver) = mpsc::channel();
+    let interrupt = conn.get_interrupt_handle();
+    thread::spawn(move || {
+        let started_at = SystemTime::now();
+        let timer = Instant::now();
+        let mut results = Vec::new();
+        let outcome = run_script(&conn, &sql_content, &parameters, &mut results);
+        // The receiver is gone only if the app quit mid-run, so there is no one left to tell
+        let _ = sender.send(RunOutcome {
+            sql: sql_content,
+            started_at,
+            duration: timer.elapsed(),
+            results,
+            error: outcome.err().map(|e| e.to_string()),
+            conn: Some(conn),
+        });
+    });
+    app.running = Some(RunningQuery {
+        script_path,
+        started: Instant::now(),
+        cancelled: false,
+        in_transaction,
+        note,
+        interrupt,
+        receiver,
+    });
+}
+
+/// Picks up the outcome of a finished run: shows its results and records it in the history ledger
+pub fn collect_finished_run(app: &mut App) {
+    let Some(running) = &app.running else {
+        return;
+    };
+    let outcome = match running.receiver.try_recv() {
+        Ok(outcome) => outcome,
+        Err(TryRecvError::Empty) => return,
+        Err(TryRecvError::Disconnected) => RunOutcome {
+            sql: String::new(),
+            started_at: SystemTime::now(),
+            duration: running.started.elapsed(),
+            results: Vec::new(),
+            error: Some("the worker thread stopped unexpectedly".to_string()),
+            conn: None,
+        },
+    };
+    let Some(running) = app.running.take() else {
+        return;
+    };
+
+    app.query_result = match &outcome.error {
+        None => format!(
+            "Executed {} statement(s) in {:.2}s.",
+            outcome.results.len(),
+            outcome.duration.as_secs_f64()
+        ),
+        Some(_) if running.cancelled => format!(
+            "Cancelled after {:.2}s; {} statement(s) had completed.",
+            outcome.duration.as_secs_f64(),
+            outcome.results.len()
+        ),
+        Some(e) => format!(
+            "Error executing statement {}: {}",
+            outcome.results.len() + 1,
+            e
+        ),
+    };
+    if let Some(note) = &running.note {
+        app.query_result.push_str(&format!(" ({})", note));
+    }
+    if outcome.error.is_some() && !running.cancelled {
+        app.report_failure(app.query_result.clone());
+    }
+    if let Some(history) = &app.history {
+        let record = RunRecord::from_results(
+            &running.script_path,
+            &outcome.sql,
+            outcome.started_at,
+            outcome.duration,
+            &outcome.results,
+            outcome.error.clone(),
+        );
+        if let Err(e) = history.record(&record) {
+            app.query_result
+                .push_str(&format!(" (Could not record run in history: {})", e));
+        }
+    }
+    app.set_results(outcome.results);
+    if running.in_transaction {
+        return_transaction_connection(app, running.script_path, outcome.conn);
+    }
+}
+
+/// Gives a finished run's connection back to the open transaction and waits for the user
+/// to commit or roll back, unless the transaction ended during the run: the script may have
+/// committed itself, or an error may have made SQLite roll back.
+fn return_transaction_connection(app: &mut App, script_path: String, conn: Option<Connection>) {
+    let Some(transaction) = &mut app.transaction else {
+        return;
+    };
+    match conn {
+        Some(conn) if !conn.is_autocommit() => {
+            transaction.scripts.push(script_path);
+            transaction.conn = Some(conn);
+            app.input_mode = InputMode::ReviewingTransaction;
+        }
+        _ => {
+            app.query_result
+                .push_str(" (The transaction ended during the run; nothing is left to commit.)");
+            app.transaction = None;
+        }
+    }
+}
+
+/// Collects the parameter names used anywhere in the script, in order of first appearance.
+///
+/// Statements are prepared (never stepped) so SQLite itself reports the parameters. If a
+/// statement can't be prepared yet, typically because it reads a table an earlier statement
+/// creates, the parameters of the rest of the script are numbered from its text instead.
+pub fn find_parameters(conn: &Connection, sql: &str) -> Vec<String> {
+    let mut names: Vec<String> = Vec::new();
+    let mut batch = Batch::new(conn, sql);
+    let mut statements = split_statements(sql).into_iter();
+    loop {
+        match batch.next() {
+            Ok(Some(stmt)) => {
+                let anonymous = anonymous_parameters(&stmt, statements.next());
+                for index in 1..=stmt.parameter_count() {
+                    if stmt.parameter_name(index).is_none() && !anonymous(index) {
+                        // A gap in `?NNN` numbering, not a parameter in the SQL
+                        continue;
+                    }
+                    let name = parameter_key(&stmt, index);
+                    if !names.contains(&name) {
+                        names.push(name);
                     }
-                } else {
-                    match conn.execute_batch(&sql_content) {
-                        Ok(_) => {
-                            let changes = conn.total_changes();
-                            app.query_result = format!(
-                                "Command executed successfully. {} rows affected.",
-                                changes
-                            );
+                }
+            }
+            Ok(None) => break,
+            Err(_) => {
+                // This statement and the rest can't be prepared yet, typically because they use
+                // a table an earlier one creates, so their parameters are numbered from the text
+                for statement in statements {
+                    for (text, index) in number_parameters(statement) {
+                        let name = if text == "?" {
+                            format!("?{}", index)
+                        } else {
+                            text
+                        };
+                        if !names.contains(&name) {
+                            names.push(name);
                         }
-                        Err(e) => app.query_result = format!("Error executing command: {}", e),
                     }
                 }
+                break;
+            }
+        }
+    }
+    names
+}
+
+/// The statements in `sql` that may write to the database, with parameters bound and
+/// whitespace collapsed so each fits on one line.
+///
+/// Statements are only prepared, never stepped. If one can't be prepared yet, typically
+/// because it uses a table an earlier statement creates, the rest of the script can't be
+/// checked and is reported as unchecked, to be on the safe side.
+pub fn find_modifications(
+    conn: &Connection,
+    sql: &str,
+    parameters: &HashMap<String, Value>,
+) -> Modifications {
+    let mut modifications = Modifications::default();
+    let mut batch = Batch::new(conn, sql);
+    loop {
+        match batch.next() {
+            Ok(Some(mut stmt)) => {
+                if stmt.readonly() {
+                    continue;
+                }
+                // A value that fails to bind shows as NULL; the run itself reports the error
+                let _ = bind_parameters(&mut stmt, parameters);
+                let statement_sql = stmt.expanded_sql().unwrap_or_default();
+                modifications.statements.push(
+                    statement_sql
+                        .split_whitespace()
+                        .collect::<Vec<_>>()
+                        .join(" "),
+                );
             }
+            Ok(None) => break,
             Err(e) => {
-                app.query_result = format!("Error reading file {}: {}", file_path, e);
+                modifications.unchecked = Some(e.to_string());
+                break;
+            }
+        }
+    }
+    modifications
+}
+
+/// The statements in `sql` that end a transaction: `COMMIT`, `END` and `ROLLBACK`, but not
+/// `ROLLBACK TO` a savepoint. Found lexically, each collapsed onto one line.
+pub fn find_transaction_ends(sql: &str) -> Vec<String> {
+    split_statements(sql)
+        .into_iter()
+        .filter(|statement| {
+            let words: Vec<String> = tokenize(statement)
+                .into_iter()
+                .filter(|token| !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment))
+                .take(3)
+                .map(|token| token.text.to_ascii_uppercase())
+                .collect();
+            match words.first().map(String::as_str) {
+                Some("COMMIT" | "END") => true,
+                Some("ROLLBACK") => !words.iter().any(|word| word == "TO"),
+                _ => false,
+            }
+        })
+        .map(|statement| statement.split_whitespace().collect::<Vec<_>>().join(" "))
+        .collect()
+}
+
+/// Binds parameters by name from `parameters`; any without a value are left NULL
+fn bind_parameters(
+    stmt: &mut Statement,
+    parameters: &HashMap<String, Value>,
+) -> Result<(), RusqliteError> {
+    for index in 1..=stmt.parameter_count() {
+        if let Some(value) = parameters.get(&parameter_key(stmt, index)) {
+            stmt.raw_bind_parameter(index, value)?;
+        }
+    }
+    Ok(())
+}
+
+/// Whether each unnamed index of `stmt` is an anonymous `?` in its text. SQLite names neither
+/// `?` nor the gaps `?NNN` leaves, so the statement's own tokens tell them apart. If they are
+/// missing or number the statement differently from SQLite, every unnamed index is kept.
+fn anonymous_parameters(stmt: &Statement, statement_sql: Option<&str>) -> impl Fn(usize) -> bool {
+    let numbered = statement_sql.map(number_parameters).filter(|numbered| {
+        let count = numbered.iter().map(|&(_, index)| index).max().unwrap_or(0);
+        count == stmt.parameter_count()
+            && numbered.iter().all(|(text, index)| {
+                text == "?" || stmt.parameter_index(text).ok().flatten() == Some(*index)
+            })
+    });
+    move |index| {
+        numbered.as_ref().is_none_or(|numbered| {
+            numbered
+                .iter()
+                .any(|(text, numbered_index)| text == "?" && *numbered_index == index)
+        })
+    }
+}
+
+/// The name a parameter is filed under: its SQL spelling, or `?N` for anonymous `?` parameters
+fn parameter_key(stmt: &Statement, index: usize) -> String {
+    stmt.parameter_name(index)
+        .map(str::to_string)
+        .unwrap_or_else(|| format!("?{}", index))
+}
+
+/// Walks every statement in `sql` in order, pushing one result per statement.
+///
+/// Parameters are bound by name from `parameters`; any without a value are left NULL.
+/// Statements that ran before a failure are kept in `results`.
+pub fn run_script(
+    conn: &Connection,
+    sql: &str,
+    parameters: &HashMap<String, Value>,
+    results: &mut Vec<StatementResult>,
+) -> Result<(), RusqliteError> {
+    let mut batch = Batch::new(conn, sql);
+    while let Some(mut stmt) = batch.next()? {
+        bind_parameters(&mut stmt, parameters)?;
+        let statement_sql = stmt.expanded_sql().unwrap_or_default().trim().to_string();
+        let kind = StatementKind::classify(&stmt);
+        let total_changes_before = conn.total_changes();
+        let started = Instant::now();
+        let mut output = match kind {
+            StatementKind::Query => StatementOutput::Rows(collect_rows(&mut stmt)?),
+            StatementKind::ReadOnly => {
+                stmt.raw_execute()?;
+                StatementOutput::Done
             }
+            StatementKind::Modification => {
+                stmt.raw_execute()?;
+                StatementOutput::Changes(0)
+            }
+        };
+        let duration = started.elapsed();
+        let changes = statement_changes(conn, total_changes_before);
+        if let StatementOutput::Changes(count) = &mut output {
+            *count = changes;
         }
+        let rows_returned = match &output {
+            StatementOutput::Rows(result_set) => result_set.rows.len(),
+            _ => 0,
+        };
+        let stats = StatementStats {
+            duration,
+            changes,
+            rows_returned,
+            fullscan_steps: stmt.get_status(StatementStatus::FullscanStep),
+            sorts: stmt.get_status(StatementStatus::Sort),
+            autoindex_rows: stmt.get_status(StatementStatus::AutoIndex),
+            vm_steps: stmt.get_status(StatementStatus::VmStep),
+        };
+        results.push(StatementResult {
+            sql: statement_sql,
+            output,
+            stats: Some(stats),
+        });
+    }
+    Ok(())
+}
+
+/// Rows the statement that just ran changed. `changes()` keeps the count of the last
+/// INSERT, UPDATE or DELETE, so after DDL it would repeat an earlier statement's count;
+/// the connection's running total only moves when this statement changed rows.
+fn statement_changes(conn: &Connection, total_changes_before: u64) -> usize {
+    if conn.total_changes() == total_changes_before {
+        0
+    } else {
+        conn.changes() as usize
+    }
+}
+
+pub fn collect_rows(stmt: &mut Statement) -> Result<ResultSet, RusqliteError> {
+    let columns: Vec<String> = stmt.column_names().iter().map(|s| s.to_string()).collect();
+    let column_count = columns.len();
+    let mut rows = Vec::new();
+    let mut raw_rows = stmt.raw_query();
+    while let Some(row) = raw_rows.next()? {
+        let mut values = Vec::with_capacity(column_count);
+        for i in 0..column_count {
+            values.push(Value::from(row.get_ref(i)?));
+        }
+        rows.push(values);
+    }
+    Ok(ResultSet { columns, rows })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn numbered_parameters_with_gaps_are_not_invented() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(
+            find_parameters(&conn, "SELECT :a, @b, $c, ?5, ?"),
+            [":a", "@b", "$c", "?5", "?6"]
+        );
+        assert_eq!(
+            find_parameters(&conn, "SELECT ?3; SELECT ?, ?, :x, ?2"),
+            ["?3", "?1", "?2", ":x"]
+        );
+    }
+
+    #[test]
+    fn parameters_of_unprepared_statements_are_numbered_from_the_text() {
+        let conn = Connection::open_in_memory().unwrap();
+        assert_eq!(
+            find_parameters(
+                &conn,
+                "CREATE TEMP TABLE q(a); SELECT * FROM q WHERE a = ? OR a = :b OR a = ?"
+            ),
+            ["?1", ":b", "?3"]
+        );
+    }
+
+    #[test]
+    fn unchecked_rest_is_not_counted_as_a_modification() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t(x)").unwrap();
+        let modifications = find_modifications(
+            &conn,
+            "SELECT 1; INSERT INTO t VALUES (1); SELECT * FROM missing; DELETE FROM t;",
+            &HashMap::new(),
+        );
+        assert_eq!(modifications.statements, ["INSERT INTO t VALUES (1);"]);
+        assert!(modifications.unchecked.unwrap().contains("missing"));
+    }
+
+    #[test]
+    fn statements_ending_a_transaction_are_found() {
+        assert_eq!(
+            find_transaction_ends(
+                "UPDATE t SET x = 1; -- done\ncommit; ROLLBACK TO sp; ROLLBACK TRANSACTION TO sp;\nEND TRANSACTION; ROLLBACK;"
+            ),
+            ["commit", "END TRANSACTION", "ROLLBACK"]
+        );
+        assert!(find_transaction_ends("SELECT 'COMMIT'; -- END").is_empty());
     }
 }
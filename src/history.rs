@@ -0,0 +1,165 @@
+use crate::db::{StatementOutput, StatementResult};
+use rusqlite::{params, Connection, OptionalExtension, Result as SqlResult};
+use sha2::{Digest, Sha256};
+use std::{
+    path::Path,
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+
+/// Number of past runs loaded into the history pane
+pub const HISTORY_PAGE_SIZE: usize = 500;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS sql_texts (
+    sql_hash TEXT PRIMARY KEY,
+    sql_text TEXT NOT NULL
+);
+CREATE TABLE IF NOT EXISTS runs (
+    id            INTEGER PRIMARY KEY,
+    script_path   TEXT NOT NULL,
+    sql_hash      TEXT NOT NULL REFERENCES sql_texts (sql_hash),
+    started_at    REAL NOT NULL,
+    duration_ms   INTEGER NOT NULL,
+    rows_returned INTEGER NOT NULL,
+    rows_affected INTEGER NOT NULL,
+    error         TEXT
+);
+CREATE INDEX IF NOT EXISTS runs_started_at ON runs (started_at);
+";
+
+/// The execution ledger: one row per script run, kept in its own SQLite file
+pub struct History {
+    conn: Connection,
+}
+
+/// Everything recorded about a single run
+pub struct RunRecord<'a> {
+    pub script_path: &'a str,
+    pub sql: &'a str,
+    pub started_at: SystemTime,
+    pub duration: Duration,
+    pub rows_returned: usize,
+    pub rows_affected: usize,
+    pub error: Option<String>,
+}
+
+impl<'a> RunRecord<'a> {
+    /// Builds a record from the statement results of a run, summing returned and affected rows
+    pub fn from_results(
+        script_path: &'a str,
+        sql: &'a str,
+        started_at: SystemTime,
+        duration: Duration,
+        results: &[StatementResult],
+        error: Option<String>,
+    ) -> Self {
+        let mut rows_returned = 0;
+        let mut rows_affected = 0;
+        for result in results {
+            match &result.output {
+                StatementOutput::Rows(result_set) => rows_returned += result_set.rows.len(),
+                StatementOutput::Changes(changes) => rows_affected += changes,
+                StatementOutput::Done | StatementOutput::Plan(_) => {}
+            }
+        }
+        Self {
+            script_path,
+            sql,
+            started_at,
+            duration,
+            rows_returned,
+            rows_affected,
+            error,
+        }
+    }
+}
+
+/// A past run, as listed in the history pane
+#[derive(Clone, Debug)]
+pub struct HistoryEntry {
+    pub script_path: String,
+    pub sql_hash: String,
+    /// Local start time, formatted by SQLite as `YYYY-MM-DD HH:MM:SS`
+    pub started_at: String,
+    pub duration_ms: i64,
+    pub rows_returned: i64,
+    pub rows_affected: i64,
+    pub error: Option<String>,
+}
+
+impl History {
+    pub fn open(path: &Path) -> SqlResult<Self> {
+        let conn = Connection::open(path)?;
+        conn.execute_batch(SCHEMA)?;
+        Ok(Self { conn })
+    }
+
+    pub fn record(&self, run: &RunRecord) -> SqlResult<()> {
+        let sql_hash = hash_sql(run.sql);
+        let started_at = run
+            .started_at
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs_f64();
+        self.conn.execute(
+            "INSERT OR IGNORE INTO sql_texts (sql_hash, sql_text) VALUES (?1, ?2)",
+            params![sql_hash, run.sql],
+        )?;
+        self.conn.execute(
+            "INSERT INTO runs (script_path, sql_hash, started_at, duration_ms, rows_returned, rows_affected, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+            params![
+                run.script_path,
+                sql_hash,
+                started_at,
+                run.duration.as_millis() as i64,
+                run.rows_returned as i64,
+                run.rows_affected as i64,
+                run.error
+            ],
+        )?;
+        Ok(())
+    }
+
+    /// The most recent runs, newest first
+    pub fn recent(&self, limit: usize) -> SqlResult<Vec<HistoryEntry>> {
+        let mut stmt = self.conn.prepare(
+            "SELECT script_path, sql_hash, datetime(started_at, 'unixepoch', 'localtime'),
+                    duration_ms, rows_returned, rows_affected, error
+             FROM runs ORDER BY started_at DESC, id DESC LIMIT ?1",
+        )?;
+        let entries = stmt
+            .query_map([limit as i64], |row| {
+                Ok(HistoryEntry {
+                    script_path: row.get(0)?,
+                    sql_hash: row.get(1)?,
+                    started_at: row.get(2)?,
+                    duration_ms: row.get(3)?,
+                    rows_returned: row.get(4)?,
+                    rows_affected: row.get(5)?,
+                    error: row.get(6)?,
+                })
+            })?
+            .collect();
+        entries
+    }
+
+    /// The exact SQL text a run executed
+    pub fn sql_text(&self, sql_hash: &str) -> SqlResult<Option<String>> {
+        self.conn
+            .query_row(
+                "SELECT sql_text FROM sql_texts WHERE sql_hash = ?1",
+                [sql_hash],
+                |row| row.get(0),
+            )
+            .optional()
+    }
+}
+
+/// Hex-encoded SHA-256 of the script text
+pub fn hash_sql(sql: &str) -> String {
+    Sha256::digest(sql.as_bytes())
+        .iter()
+        .map(|b| format!("{:02x}", b))
+        .collect()
+}
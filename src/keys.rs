@@ -0,0 +1,636 @@
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::Deserialize;
+use std::{collections::BTreeMap, fmt};
+
+/// Something the user can do from Normal mode, or in one of the confirmation popups.
+/// Navigation actions act on the focused pane.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Action {
+    Quit,
+    Help,
+    SwitchFocus,
+    Back,
+    CancelRun,
+    Down,
+    Up,
+    Left,
+    Right,
+    Select,
+    PageDown,
+    PageUp,
+    FirstRow,
+    LastRow,
+    RunInTransaction,
+    Explain,
+    Edit,
+    NewScript,
+    NewFolder,
+    NewScriptFromTable,
+    Delete,
+    Rename,
+    Search,
+    ToggleSchema,
+    NextResult,
+    PreviousResult,
+    ToggleBytecode,
+    InspectRow,
+    CopyCell,
+    CopyRow,
+    CopyCsv,
+    CopyMarkdown,
+    Sort,
+    Filter,
+    ClearFilters,
+    Export,
+    History,
+    PickDatabase,
+    ToggleReadOnly,
+    Confirm,
+    Cancel,
+    Commit,
+    RollBack,
+    KeepTransactionOpen,
+    QuitAndRollBack,
+}
+
+/// Where an action's keys are read. A key is only taken from other actions in the same scope,
+/// so a popup can reuse keys that mean something else in Normal mode.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum KeyScope {
+    Normal,
+    /// The popups confirming a run or a delete
+    Confirmation,
+    /// The popup reviewing an open transaction
+    Transaction,
+}
+
+impl Action {
+    /// Every action, in the order the help popup lists them
+    pub const ALL: [Action; 45] = [
+        Action::Down,
+        Action::Up,
+        Action::Left,
+        Action::Right,
+        Action::Select,
+        Action::Back,
+        Action::CancelRun,
+        Action::SwitchFocus,
+        Action::PageDown,
+        Action::PageUp,
+        Action::FirstRow,
+        Action::LastRow,
+        Action::RunInTransaction,
+        Action::Explain,
+        Action::Search,
+        Action::ToggleSchema,
+        Action::NewScriptFromTable,
+        Action::Edit,
+        Action::NewScript,
+        Action::NewFolder,
+        Action::Delete,
+        Action::Rename,
+        Action::NextResult,
+        Action::PreviousResult,
+        Action::ToggleBytecode,
+        Action::InspectRow,
+        Action::CopyCell,
+        Action::CopyRow,
+        Action::CopyCsv,
+        Action::CopyMarkdown,
+        Action::Sort,
+        Action::Filter,
+        Action::ClearFilters,
+        Action::Export,
+        Action::History,
+        Action::PickDatabase,
+        Action::ToggleReadOnly,
+        Action::Help,
+        Action::Quit,
+        Action::Confirm,
+        Action::Cancel,
+        Action::Commit,
+        Action::RollBack,
+        Action::KeepTransactionOpen,
+        Action::QuitAndRollBack,
+    ];
+
+    /// The name used for the action under `[keys]` in config.toml
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::Quit => "quit",
+            Action::Help => "help",
+            Action::SwitchFocus => "switch_focus",
+            Action::Back => "back",
+            Action::CancelRun => "cancel_run",
+            Action::Down => "down",
+            Action::Up => "up",
+            Action::Left => "left",
+            Action::Right => "right",
+            Action::Select => "select",
+            Action::PageDown => "page_down",
+            Action::PageUp => "page_up",
+            Action::FirstRow => "first_row",
+            Action::LastRow => "last_row",
+            Action::RunInTransaction => "run_in_transaction",
+            Action::Explain => "explain",
+            Action::Edit => "edit",
+            Action::NewScript => "new_script",
+            Action::NewFolder => "new_folder",
+            Action::NewScriptFromTable => "new_script_from_table",
+            Action::Delete => "delete",
+            Action::Rename => "rename",
+            Action::Search => "search",
+            Action::ToggleSchema => "toggle_schema",
+            Action::NextResult => "next_result",
+            Action::PreviousResult => "previous_result",
+            Action::ToggleBytecode => "toggle_bytecode",
+            Action::InspectRow => "inspect_row",
+            Action::CopyCell => "copy_cell",
+            Action::CopyRow => "copy_row",
+            Action::CopyCsv => "copy_csv",
+            Action::CopyMarkdown => "copy_markdown",
+            Action::Sort => "sort",
+            Action::Filter => "filter",
+            Action::ClearFilters => "clear_filters",
+            Action::Export => "export",
+            Action::History => "history",
+            Action::PickDatabase => "pick_database",
+            Action::ToggleReadOnly => "toggle_read_only",
+            Action::Confirm => "confirm",
+            Action::Cancel => "cancel",
+            Action::Commit => "commit",
+            Action::RollBack => "roll_back",
+            Action::KeepTransactionOpen => "keep_transaction_open",
+            Action::QuitAndRollBack => "quit_and_roll_back",
+        }
+    }
+
+    /// What the action does, as shown in the help popup
+    pub fn description(self) -> &'static str {
+        match self {
+            Action::Quit => "Quit",
+            Action::Help => "Toggle this help message",
+            Action::SwitchFocus => "Switch focus between the list and results",
+            Action::Back => "Cancel a run / clear the search / leave schema or results",
+            Action::CancelRun => "Cancel the running script, or the text being typed",
+            Action::Down => "Move down",
+            Action::Up => "Move up",
+            Action::Left => "Collapse folder / previous column",
+            Action::Right => "Run script or expand folder / browse table / next column",
+            Action::Select => "Run script or toggle folder / browse table / inspect cell",
+            Action::PageDown => "Scroll the preview or results down a page",
+            Action::PageUp => "Scroll the preview or results up a page",
+            Action::FirstRow => "First result row",
+            Action::LastRow => "Last result row",
+            Action::RunInTransaction => "Run in a transaction, then commit or roll back",
+            Action::Explain => "Explain the query plan without running",
+            Action::Edit => "Edit selected script",
+            Action::NewScript => "Add a new script in the selected folder",
+            Action::NewFolder => "Create a folder",
+            Action::NewScriptFromTable => "New SELECT script from table (schema)",
+            Action::Delete => "Delete selected script or empty folder",
+            Action::Rename => "Rename selected script or folder",
+            Action::Search => "Search scripts (switch_focus also searches contents)",
+            Action::ToggleSchema => "Toggle the schema browser",
+            Action::NextResult => "Next statement result",
+            Action::PreviousResult => "Previous statement result",
+            Action::ToggleBytecode => "Toggle plan tree/bytecode (explained results)",
+            Action::InspectRow => "Inspect selected row (results)",
+            Action::CopyCell => "Copy cell to clipboard (results)",
+            Action::CopyRow => "Copy row to clipboard (results)",
+            Action::CopyCsv => "Copy result as CSV (results)",
+            Action::CopyMarkdown => "Copy result as Markdown (results)",
+            Action::Sort => "Sort browsed table by column (results)",
+            Action::Filter => "Filter browsed table by column (results)",
+            Action::ClearFilters => "Clear browsed table filters (results)",
+            Action::Export => "Export the current result set",
+            Action::History => "Browse the run history",
+            Action::PickDatabase => "Switch database",
+            Action::ToggleReadOnly => "Toggle read-only mode",
+            Action::Confirm => "Run or delete (confirmation popup)",
+            Action::Cancel => "Cancel (confirmation popup)",
+            Action::Commit => "Commit (transaction popup)",
+            Action::RollBack => "Roll back (transaction popup)",
+            Action::KeepTransactionOpen => "Keep the transaction open (transaction popup)",
+            Action::QuitAndRollBack => "Quit, rolling back (transaction popup)",
+        }
+    }
+
+    fn scope(self) -> KeyScope {
+        match self {
+            Action::Confirm | Action::Cancel => KeyScope::Confirmation,
+            Action::Commit
+            | Action::RollBack
+            | Action::KeepTransactionOpen
+            | Action::QuitAndRollBack => KeyScope::Transaction,
+            _ => KeyScope::Normal,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Self> {
+        Action::ALL.into_iter().find(|action| action.name() == name)
+    }
+
+    /// The keys bound to the action unless `[keys]` says otherwise
+    fn default_chords(self) -> &'static [&'static str] {
+        match self {
+            Action::Quit => &["q"],
+            Action::Help => &["?"],
+            Action::SwitchFocus => &["Tab"],
+            Action::Back => &["Esc"],
+            Action::CancelRun => &["Ctrl-c"],
+            Action::Down => &["j", "Down"],
+            Action::Up => &["k", "Up"],
+            Action::Left => &["h", "Left"],
+            Action::Right => &["l", "Right"],
+            Action::Select => &["Enter"],
+            Action::PageDown => &["PageDown", "Ctrl-d"],
+            Action::PageUp => &["PageUp", "Ctrl-u"],
+            Action::FirstRow => &["g", "Home"],
+            Action::LastRow => &["G", "End"],
+            Action::RunInTransaction => &["t"],
+            Action::Explain => &["E"],
+            Action::Edit => &["e"],
+            Action::NewScript => &["a"],
+            Action::NewFolder => &["A"],
+            Action::NewScriptFromTable => &["n"],
+            Action::Delete => &["d"],
+            Action::Rename => &["r"],
+            Action::Search => &["/"],
+            Action::ToggleSchema => &["S"],
+            Action::NextResult => &["]"],
+            Action::PreviousResult => &["["],
+            Action::ToggleBytecode => &["b"],
+            Action::InspectRow => &["v"],
+            Action::CopyCell => &["y"],
+            Action::CopyRow => &["Y"],
+            Action::CopyCsv => &["C"],
+            Action::CopyMarkdown => &["M"],
+            Action::Sort => &["s"],
+            Action::Filter => &["f"],
+            Action::ClearFilters => &["F"],
+            Action::Export => &["x"],
+            Action::History => &["H"],
+            Action::PickDatabase => &["D"],
+            Action::ToggleReadOnly => &["R"],
+            Action::Confirm => &["y"],
+            Action::Cancel => &["n", "Esc"],
+            Action::Commit => &["c"],
+            Action::RollBack => &["r"],
+            Action::KeepTransactionOpen => &["Esc"],
+            Action::QuitAndRollBack => &["Q"],
+        }
+    }
+}
+
+/// A key together with the modifiers held with it, e.g. `Ctrl-d`
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct KeyChord {
+    pub code: KeyCode,
+    pub modifiers: KeyModifiers,
+}
+
+impl KeyChord {
+    fn from_event(key: &KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+
+    /// Brings a key into the form terminals report it in, so configured chords match presses.
+    /// Shift is folded into the character for letters and symbols, since terminals report `G`
+    /// both with and without it; Ctrl letters are lowercase, since terminals can't tell `Ctrl-D`
+    /// from `Ctrl-d`; and Shift-Tab is `BackTab`.
+    fn new(code: KeyCode, modifiers: KeyModifiers) -> Self {
+        let modifiers =
+            modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT | KeyModifiers::SHIFT);
+        let code = match code {
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::CONTROL) => {
+                KeyCode::Char(c.to_ascii_lowercase())
+            }
+            KeyCode::Char(c) if modifiers.contains(KeyModifiers::SHIFT) => {
+                KeyCode::Char(c.to_ascii_uppercase())
+            }
+            KeyCode::Tab if modifiers.contains(KeyModifiers::SHIFT) => KeyCode::BackTab,
+            code => code,
+        };
+        let modifiers = match code {
+            // Shift-Tab arrives as BackTab, with or without the modifier
+            KeyCode::Char(_) | KeyCode::BackTab => modifiers - KeyModifiers::SHIFT,
+            _ => modifiers,
+        };
+        Self { code, modifiers }
+    }
+
+    /// Parses chords such as `q`, `G`, `Enter`, `PageDown`, `F5`, `Ctrl-d` or `Alt+Left`
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut modifiers = KeyModifiers::NONE;
+        let mut rest = text.trim();
+        // A lone `-` or `+` is a key, not a separator
+        while let Some((prefix, key)) = rest
+            .split_once(['-', '+'])
+            .filter(|(_, key)| !key.is_empty())
+        {
+            modifiers |= match prefix.to_lowercase().as_str() {
+                "ctrl" | "control" => KeyModifiers::CONTROL,
+                "alt" | "meta" => KeyModifiers::ALT,
+                "shift" => KeyModifiers::SHIFT,
+                _ => return Err(format!("Unknown modifier '{}' in key '{}'", prefix, text)),
+            };
+            rest = key;
+        }
+        let code = if rest.chars().count() == 1 {
+            KeyCode::Char(rest.chars().next().unwrap_or_default())
+        } else {
+            match rest.to_lowercase().as_str() {
+                "enter" | "return" => KeyCode::Enter,
+                "esc" | "escape" => KeyCode::Esc,
+                "tab" => KeyCode::Tab,
+                "backtab" => KeyCode::BackTab,
+                "backspace" => KeyCode::Backspace,
+                "delete" | "del" => KeyCode::Delete,
+                "insert" | "ins" => KeyCode::Insert,
+                "space" => KeyCode::Char(' '),
+                "up" => KeyCode::Up,
+                "down" => KeyCode::Down,
+                "left" => KeyCode::Left,
+                "right" => KeyCode::Right,
+                "pageup" | "pgup" => KeyCode::PageUp,
+                "pagedown" | "pgdn" => KeyCode::PageDown,
+                "home" => KeyCode::Home,
+                "end" => KeyCode::End,
+                name => match name.strip_prefix('f').and_then(|n| n.parse::<u8>().ok()) {
+                    Some(n) if (1..=12).contains(&n) => KeyCode::F(n),
+                    _ => return Err(format!("Unknown key '{}'", text)),
+                },
+            }
+        };
+        Ok(Self::new(code, modifiers))
+    }
+}
+
+impl fmt::Display for KeyChord {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "Ctrl-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "Alt-")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "Shift-")?;
+        }
+        match self.code {
+            KeyCode::Char(' ') => write!(f, "Space"),
+            KeyCode::Char(c) => write!(f, "{}", c),
+            KeyCode::F(n) => write!(f, "F{}", n),
+            KeyCode::PageUp => write!(f, "PgUp"),
+            KeyCode::PageDown => write!(f, "PgDn"),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+/// Whether a chord types a character into a text input
+fn is_text(chord: &KeyChord) -> bool {
+    matches!(chord.code, KeyCode::Char(_))
+        && !chord
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+}
+
+/// One key or a list of keys, as written under `[keys]`
+#[derive(Deserialize, Debug, Clone)]
+#[serde(untagged)]
+pub enum KeyList {
+    One(String),
+    Many(Vec<String>),
+}
+
+impl KeyList {
+    fn chords(&self) -> Vec<&str> {
+        match self {
+            KeyList::One(chord) => vec![chord.as_str()],
+            KeyList::Many(chords) => chords.iter().map(String::as_str).collect(),
+        }
+    }
+}
+
+/// The keys bound to each action: the defaults, with `[keys]` from config.toml applied
+#[derive(Debug)]
+pub struct KeyMap {
+    bindings: Vec<(Action, Vec<KeyChord>)>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        let bindings = Action::ALL
+            .into_iter()
+            .map(|action| {
+                let chords = action
+                    .default_chords()
+                    .iter()
+                    .filter_map(|chord| KeyChord::parse(chord).ok())
+                    .collect();
+                (action, chords)
+            })
+            .collect();
+        Self { bindings }
+    }
+}
+
+impl KeyMap {
+    /// Applies the configured bindings over the defaults. A configured key is taken away from
+    /// any action in the same scope it was bound to by default. Unknown actions and keys are
+    /// skipped and reported in the returned messages.
+    pub fn from_config(keys: &BTreeMap<String, KeyList>) -> (Self, Vec<String>) {
+        let mut keymap = Self::default();
+        let mut problems = Vec::new();
+        for (name, list) in keys {
+            let Some(action) = Action::from_name(name) else {
+                problems.push(format!("Unknown action '{}' in [keys]", name));
+                continue;
+            };
+            let mut chords = Vec::new();
+            for text in list.chords() {
+                match KeyChord::parse(text) {
+                    Ok(chord) => chords.push(chord),
+                    Err(e) => problems.push(e),
+                }
+            }
+            // Keep the defaults rather than leave the action unreachable over a typo
+            if chords.is_empty() && !list.chords().is_empty() {
+                continue;
+            }
+            for (other, bound) in &mut keymap.bindings {
+                if other.scope() == action.scope() {
+                    bound.retain(|chord| !chords.contains(chord));
+                }
+            }
+            if let Some((_, bound)) = keymap.bindings.iter_mut().find(|(a, _)| *a == action) {
+                *bound = chords;
+            }
+        }
+        (keymap, problems)
+    }
+
+    /// The Normal-mode action bound to a key press, if any
+    pub fn action_for(&self, key: &KeyEvent) -> Option<Action> {
+        let chord = KeyChord::from_event(key);
+        self.bindings
+            .iter()
+            .find(|(action, chords)| action.scope() == KeyScope::Normal && chords.contains(&chord))
+            .map(|&(action, _)| action)
+    }
+
+    /// The Normal-mode action bound to a key press in a popup being typed in. Characters
+    /// without Ctrl or Alt are text there, so they never count as an action.
+    pub fn input_action_for(&self, key: &KeyEvent) -> Option<Action> {
+        if is_text(&KeyChord::from_event(key)) {
+            None
+        } else {
+            self.action_for(key)
+        }
+    }
+
+    /// Whether a key press is bound to `action`, for popups that only know a few actions
+    pub fn is_bound(&self, key: &KeyEvent, action: Action) -> bool {
+        let chord = KeyChord::from_event(key);
+        self.bindings
+            .iter()
+            .any(|(a, chords)| *a == action && chords.contains(&chord))
+    }
+
+    /// The keys bound to an action, e.g. `j/Down`, or `-` if it has none
+    pub fn describe(&self, action: Action) -> String {
+        let chords: Vec<String> = self
+            .bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .map(|(_, chords)| chords.iter().map(ToString::to_string).collect())
+            .unwrap_or_default();
+        if chords.is_empty() {
+            "-".to_string()
+        } else {
+            chords.join("/")
+        }
+    }
+
+    /// The first key bound to an action, for the short hints on popup borders
+    pub fn first_key(&self, action: Action) -> String {
+        self.first_key_where(action, |_| true)
+    }
+
+    /// The first key bound to an action that isn't text, for hints on popups being typed in
+    pub fn first_input_key(&self, action: Action) -> String {
+        self.first_key_where(action, |chord| !is_text(chord))
+    }
+
+    fn first_key_where(&self, action: Action, usable: impl Fn(&KeyChord) -> bool) -> String {
+        self.bindings
+            .iter()
+            .find(|(a, _)| *a == action)
+            .and_then(|(_, chords)| chords.iter().find(|chord| usable(chord)))
+            .map_or_else(|| "-".to_string(), ToString::to_string)
+    }
+
+    /// `Press [Enter] to <verb>, [Esc] to cancel.`, for the status line under a popup being
+    /// typed in
+    pub fn input_prompt(&self, verb: &str) -> String {
+        format!(
+            "Press [{}] to {}, [{}] to cancel.",
+            self.first_input_key(Action::Select),
+            verb,
+            self.first_input_key(Action::Back)
+        )
+    }
+
+    /// One help line per action: its keys, what it does and its `[keys]` name
+    pub fn help_lines(&self) -> Vec<String> {
+        Action::ALL
+            .into_iter()
+            .map(|action| {
+                format!(
+                    "{:<16} {} [{}]",
+                    self.describe(action),
+                    action.description(),
+                    action.name()
+                )
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(code: KeyCode) -> KeyEvent {
+        KeyEvent::new(code, KeyModifiers::NONE)
+    }
+
+    #[test]
+    fn popup_keys_are_rebound_within_their_own_scope() {
+        let keys = BTreeMap::from([
+            ("roll_back".to_string(), KeyList::One("d".to_string())),
+            ("export".to_string(), KeyList::One("r".to_string())),
+        ]);
+        let (keymap, problems) = KeyMap::from_config(&keys);
+        assert!(problems.is_empty());
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('d'))),
+            Some(Action::Delete)
+        );
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('r'))),
+            Some(Action::Export)
+        );
+        assert!(keymap.is_bound(&press(KeyCode::Char('d')), Action::RollBack));
+        assert!(!keymap.is_bound(&press(KeyCode::Char('r')), Action::RollBack));
+        // Popup actions never answer a Normal-mode key press
+        assert_eq!(
+            keymap.action_for(&press(KeyCode::Char('y'))),
+            Some(Action::CopyCell)
+        );
+        assert_eq!(keymap.describe(Action::Cancel), "n/Esc");
+    }
+
+    #[test]
+    fn characters_are_text_in_input_popups() {
+        let keys = BTreeMap::from([(
+            "down".to_string(),
+            KeyList::Many(vec!["J".to_string(), "Down".to_string()]),
+        )]);
+        let (keymap, _) = KeyMap::from_config(&keys);
+        assert_eq!(keymap.input_action_for(&press(KeyCode::Char('J'))), None);
+        assert_eq!(
+            keymap.input_action_for(&press(KeyCode::Down)),
+            Some(Action::Down)
+        );
+        assert_eq!(
+            keymap.input_action_for(&KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL)),
+            Some(Action::CancelRun)
+        );
+        assert_eq!(keymap.first_key(Action::Down), "J");
+        assert_eq!(keymap.first_input_key(Action::Down), "Down");
+    }
+
+    #[test]
+    fn configured_chords_match_what_terminals_send() {
+        let keys = BTreeMap::from([
+            ("page_down".to_string(), KeyList::One("Ctrl-D".to_string())),
+            (
+                "previous_result".to_string(),
+                KeyList::One("Shift-Tab".to_string()),
+            ),
+        ]);
+        let (keymap, _) = KeyMap::from_config(&keys);
+        let ctrl_d = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        assert_eq!(keymap.action_for(&ctrl_d), Some(Action::PageDown));
+        let back_tab = KeyEvent::new(KeyCode::BackTab, KeyModifiers::SHIFT);
+        assert_eq!(keymap.action_for(&back_tab), Some(Action::PreviousResult));
+        assert_eq!(keymap.describe(Action::PreviousResult), "BackTab");
+        assert_eq!(
+            keymap.action_for(&KeyEvent::new(KeyCode::Char('g'), KeyModifiers::SHIFT)),
+            Some(Action::LastRow)
+        );
+    }
+}
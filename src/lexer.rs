@@ -0,0 +1,386 @@
+/// What a piece of SQL text is, as far as highlighting and parameter discovery care
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TokenKind {
+    Keyword,
+    /// A bare or quoted (`"x"`, `` `x` ``, `[x]`) name
+    Identifier,
+    /// A string or blob literal
+    String,
+    Number,
+    Comment,
+    /// `?`, `?NNN`, `:name`, `@name` or `$name`
+    Parameter,
+    Whitespace,
+    /// Operators and punctuation
+    Symbol,
+}
+
+/// A slice of the source SQL and what kind of token it is
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Token<'a> {
+    pub kind: TokenKind,
+    pub text: &'a str,
+}
+
+/// Splits SQL into tokens; concatenating every token's text gives back the input.
+///
+/// Unterminated strings and comments run to the end of the input, so half-written scripts
+/// still tokenise.
+pub fn tokenize(sql: &str) -> Vec<Token<'_>> {
+    let chars: Vec<(usize, char)> = sql.char_indices().collect();
+    let char_at = |i: usize| chars.get(i).map(|&(_, c)| c);
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let start = i;
+        let c = chars[i].1;
+        let kind = match c {
+            quote @ ('\'' | '"' | '`') => {
+                i += 1;
+                while i < chars.len() {
+                    if chars[i].1 == quote {
+                        // A doubled quote is an escaped quote, not the end of the literal
+                        if char_at(i + 1) == Some(quote) {
+                            i += 1;
+                        } else {
+                            break;
+                        }
+                    }
+                    i += 1;
+                }
+                i += 1;
+                if quote == '\'' {
+                    TokenKind::String
+                } else {
+                    TokenKind::Identifier
+                }
+            }
+            '[' => {
+                while i < chars.len() && chars[i].1 != ']' {
+                    i += 1;
+                }
+                i += 1;
+                TokenKind::Identifier
+            }
+            '-' if char_at(i + 1) == Some('-') => {
+                while i < chars.len() && chars[i].1 != '\n' {
+                    i += 1;
+                }
+                TokenKind::Comment
+            }
+            '/' if char_at(i + 1) == Some('*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i].1 == '*' && char_at(i + 1) == Some('/')) {
+                    i += 1;
+                }
+                i += 2;
+                TokenKind::Comment
+            }
+            'x' | 'X' if char_at(i + 1) == Some('\'') => {
+                i += 2;
+                while i < chars.len() && chars[i].1 != '\'' {
+                    i += 1;
+                }
+                i += 1;
+                TokenKind::String
+            }
+            prefix @ (':' | '@' | '$' | '?') => {
+                i += 1;
+                while i < chars.len() && is_parameter_char(prefix, chars[i].1) {
+                    i += 1;
+                }
+                if i == start + 1 && prefix != '?' {
+                    TokenKind::Symbol
+                } else {
+                    TokenKind::Parameter
+                }
+            }
+            c if c.is_ascii_digit()
+                || (c == '.' && char_at(i + 1).is_some_and(|n| n.is_ascii_digit())) =>
+            {
+                i = scan_number(&chars, i);
+                TokenKind::Number
+            }
+            c if c.is_alphabetic() || c == '_' => {
+                while i < chars.len() && (chars[i].1.is_alphanumeric() || chars[i].1 == '_') {
+                    i += 1;
+                }
+                let word = &sql[chars[start].0..byte_offset(&chars, sql, i)];
+                if is_keyword(word) {
+                    TokenKind::Keyword
+                } else {
+                    TokenKind::Identifier
+                }
+            }
+            c if c.is_whitespace() => {
+                while i < chars.len() && chars[i].1.is_whitespace() {
+                    i += 1;
+                }
+                TokenKind::Whitespace
+            }
+            _ => {
+                i += 1;
+                TokenKind::Symbol
+            }
+        };
+        i = i.min(chars.len());
+        tokens.push(Token {
+            kind,
+            text: &sql[chars[start].0..byte_offset(&chars, sql, i)],
+        });
+    }
+    tokens
+}
+
+/// Splits a script into its statements, without surrounding comments or the terminating `;`.
+///
+/// As in the `sqlite3` shell, a semicolon inside a `CREATE TRIGGER` only ends the statement
+/// after the body's closing `END`. Statements that are only comments are dropped.
+pub fn split_statements(sql: &str) -> Vec<&str> {
+    let mut statements = Vec::new();
+    // Byte range from the statement's first token to the end of its last one
+    let mut start = 0;
+    let mut end = 0;
+    let mut offset = 0;
+    // The words that decide whether this is a trigger, and the last word seen
+    let mut leading_words: Vec<String> = Vec::new();
+    let mut last_word = String::new();
+    let mut has_code = false;
+    for token in tokenize(sql) {
+        let token_start = offset;
+        offset += token.text.len();
+        match token.kind {
+            TokenKind::Whitespace | TokenKind::Comment => continue,
+            TokenKind::Symbol if token.text == ";" => {
+                let in_trigger = matches!(
+                    leading_words
+                        .iter()
+                        .map(String::as_str)
+                        .collect::<Vec<_>>()
+                        .as_slice(),
+                    ["CREATE", "TRIGGER", ..] | ["CREATE", "TEMP" | "TEMPORARY", "TRIGGER", ..]
+                );
+                if in_trigger && last_word != "END" {
+                    continue;
+                }
+                if has_code {
+                    statements.push(&sql[start..end]);
+                }
+                leading_words.clear();
+                has_code = false;
+            }
+            _ => {
+                if !has_code {
+                    start = token_start;
+                }
+                has_code = true;
+                end = offset;
+                last_word = token.text.to_ascii_uppercase();
+                if leading_words.len() < 3 {
+                    leading_words.push(last_word.clone());
+                }
+            }
+        }
+    }
+    if has_code {
+        statements.push(&sql[start..end]);
+    }
+    statements
+}
+
+fn byte_offset(chars: &[(usize, char)], sql: &str, i: usize) -> usize {
+    chars.get(i).map_or(sql.len(), |&(offset, _)| offset)
+}
+
+/// Decimal (`1`, `1.5`, `.5`, `1e-3`) or hexadecimal (`0x1F`) literals
+fn scan_number(chars: &[(usize, char)], mut i: usize) -> usize {
+    let char_at = |i: usize| chars.get(i).map(|&(_, c)| c);
+    if char_at(i) == Some('0') && matches!(char_at(i + 1), Some('x' | 'X')) {
+        i += 2;
+        while char_at(i).is_some_and(|c| c.is_ascii_hexdigit()) {
+            i += 1;
+        }
+        return i;
+    }
+    while char_at(i).is_some_and(|c| c.is_ascii_digit() || c == '.') {
+        i += 1;
+    }
+    if matches!(char_at(i), Some('e' | 'E')) {
+        let exponent = if matches!(char_at(i + 1), Some('+' | '-')) {
+            i + 2
+        } else {
+            i + 1
+        };
+        if char_at(exponent).is_some_and(|c| c.is_ascii_digit()) {
+            i = exponent;
+            while char_at(i).is_some_and(|c| c.is_ascii_digit()) {
+                i += 1;
+            }
+        }
+    }
+    i
+}
+
+fn is_parameter_char(prefix: char, c: char) -> bool {
+    if prefix == '?' {
+        c.is_ascii_digit()
+    } else {
+        c.is_alphanumeric() || c == '_'
+    }
+}
+
+fn is_keyword(word: &str) -> bool {
+    let upper = word.to_ascii_uppercase();
+    KEYWORDS.binary_search(&upper.as_str()).is_ok()
+}
+
+/// SQLite's keywords, sorted so they can be binary searched
+const KEYWORDS: [&str; 147] = [
+    "ABORT",
+    "ACTION",
+    "ADD",
+    "AFTER",
+    "ALL",
+    "ALTER",
+    "ALWAYS",
+    "ANALYZE",
+    "AND",
+    "AS",
+    "ASC",
+    "ATTACH",
+    "AUTOINCREMENT",
+    "BEFORE",
+    "BEGIN",
+    "BETWEEN",
+    "BY",
+    "CASCADE",
+    "CASE",
+    "CAST",
+    "CHECK",
+    "COLLATE",
+    "COLUMN",
+    "COMMIT",
+    "CONFLICT",
+    "CONSTRAINT",
+    "CREATE",
+    "CROSS",
+    "CURRENT",
+    "CURRENT_DATE",
+    "CURRENT_TIME",
+    "CURRENT_TIMESTAMP",
+    "DATABASE",
+    "DEFAULT",
+    "DEFERRABLE",
+    "DEFERRED",
+    "DELETE",
+    "DESC",
+    "DETACH",
+    "DISTINCT",
+    "DO",
+    "DROP",
+    "EACH",
+    "ELSE",
+    "END",
+    "ESCAPE",
+    "EXCEPT",
+    "EXCLUDE",
+    "EXCLUSIVE",
+    "EXISTS",
+    "EXPLAIN",
+    "FAIL",
+    "FILTER",
+    "FIRST",
+    "FOLLOWING",
+    "FOR",
+    "FOREIGN",
+    "FROM",
+    "FULL",
+    "GENERATED",
+    "GLOB",
+    "GROUP",
+    "GROUPS",
+    "HAVING",
+    "IF",
+    "IGNORE",
+    "IMMEDIATE",
+    "IN",
+    "INDEX",
+    "INDEXED",
+    "INITIALLY",
+    "INNER",
+    "INSERT",
+    "INSTEAD",
+    "INTERSECT",
+    "INTO",
+    "IS",
+    "ISNULL",
+    "JOIN",
+    "KEY",
+    "LAST",
+    "LEFT",
+    "LIKE",
+    "LIMIT",
+    "MATCH",
+    "MATERIALIZED",
+    "NATURAL",
+    "NO",
+    "NOT",
+    "NOTHING",
+    "NOTNULL",
+    "NULL",
+    "NULLS",
+    "OF",
+    "OFFSET",
+    "ON",
+    "OR",
+    "ORDER",
+    "OTHERS",
+    "OUTER",
+    "OVER",
+    "PARTITION",
+    "PLAN",
+    "PRAGMA",
+    "PRECEDING",
+    "PRIMARY",
+    "QUERY",
+    "RAISE",
+    "RANGE",
+    "RECURSIVE",
+    "REFERENCES",
+    "REGEXP",
+    "REINDEX",
+    "RELEASE",
+    "RENAME",
+    "REPLACE",
+    "RESTRICT",
+    "RETURNING",
+    "RIGHT",
+    "ROLLBACK",
+    "ROW",
+    "ROWS",
+    "SAVEPOINT",
+    "SELECT",
+    "SET",
+    "TABLE",
+    "TEMP",
+    "TEMPORARY",
+    "THEN",
+    "TIES",
+    "TO",
+    "TRANSACTION",
+    "TRIGGER",
+    "UNBOUNDED",
+    "UNION",
+    "UNIQUE",
+    "UPDATE",
+    "USING",
+    "VACUUM",
+    "VALUES",
+    "VIEW",
+    "VIRTUAL",
+    "WHEN",
+    "WHERE",
+    "WINDOW",
+    "WITH",
+    "WITHOUT",
+];
@@ -1,19 +1,46 @@
 // ‼️ Declare the new modules
 mod app;
+mod browser;
+mod cli;
+mod clipboard;
 mod config;
 mod db;
 mod editor;
+mod history;
+mod keys;
+mod lexer;
+mod output;
+mod params;
+mod plan;
+mod schema;
+mod search;
+mod theme;
 mod ui;
+mod value;
+mod watcher;
 
 use crate::{
-    app::{App, InputMode},
-    config::{load_config, CONFIG_DIR_NAME, CONFIG_FILE_NAME, DB_NAME},
-    db::execute_sql,
+    app::{App, Focus, InputMode, InspectTarget, LeftPane, YankTarget},
+    cli::{parse_args, run_command, Command, Context, USAGE},
+    clipboard::copy_to_clipboard,
+    config::{
+        choose_database, configured_databases, load_config, CONFIG_DIR_NAME, CONFIG_FILE_NAME,
+        DB_NAME, HISTORY_DB_NAME, PARAMETERS_FILE_NAME,
+    },
+    db::{
+        collect_finished_run, end_transaction, execute_sql, execute_sql_in_transaction,
+        execute_sql_with_parameters, explain_sql, run_confirmed,
+    },
     editor::open_editor,
+    keys::{Action, KeyMap},
+    theme::Theme,
     ui::ui,
+    value::ValueFormat,
 };
 use crossterm::{
-    event::{self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEventKind},
+    event::{
+        self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    },
     execute,
     terminal::{disable_raw_mode, enable_raw_mode, EnterAlternateScreen, LeaveAlternateScreen},
 };
@@ -23,9 +50,22 @@ use std::{
     fs,
     io::{self, stdout},
     path::Path,
+    process::ExitCode,
+    time::Duration,
 };
 
-fn main() -> io::Result<()> {
+/// How often the TUI redraws while waiting for input
+const TICK_RATE: Duration = Duration::from_millis(100);
+
+fn main() -> io::Result<ExitCode> {
+    let (command, options) = match parse_args(std::env::args().skip(1)) {
+        Ok(parsed) => parsed,
+        Err(message) => {
+            eprintln!("{}\n\n{}", message, USAGE);
+            return Ok(ExitCode::from(2));
+        }
+    };
+
     let config_dir_path = dirs::config_dir()
         .ok_or_else(|| io::Error::new(io::ErrorKind::NotFound, "Could not find config directory"))?
         .join(CONFIG_DIR_NAME);
@@ -43,28 +83,90 @@ fn main() -> io::Result<()> {
     let script_dir_path = Path::new(&script_dir_path_str).to_path_buf();
     fs::create_dir_all(&script_dir_path)?;
 
-    let db_path = data_dir_path.join(DB_NAME);
+    let mut databases = configured_databases(&config, &data_dir_path);
+    let active_database = match choose_database(
+        &mut databases,
+        options.database.as_deref(),
+        config.default_database.as_deref(),
+    ) {
+        Ok(index) => index,
+        Err(message) => {
+            eprintln!("{}", message);
+            return Ok(ExitCode::from(2));
+        }
+    };
 
+    let (keymap, key_problems) = KeyMap::from_config(&config.keys);
     if !config_path.exists() {
         fs::write(
             &config_path,
-            "# Configuration for sqledger\n# Directory where .sql scripts are stored.\n# You can use '~' for your home directory.\nscript_directory = \"~/.config/sqledger/scripts\"\n",
+            format!("# Configuration for sqledger\n# Directory where .sql scripts are stored.\n# You can use '~' for your home directory.\nscript_directory = \"~/.config/sqledger/scripts\"\n\n# Decimal places shown for REAL values. Leave unset for the shortest exact form.\n# CSV, TSV and JSON output always write the exact value.\n# float_precision = 2\n\n# Named databases to run scripts against. Press '{}' in the TUI to switch.\n# A script starting with a '-- @db: <name>' comment always runs against that database.\n# default_database = \"local\"\n# [databases]\n# local = \"~/.local/share/sqledger/scripts.db\"\n# staging = \"~/db/staging.db\"\n\n# Open databases read-only, so no script can modify them. Press '{}' in the TUI to toggle.\n# read_only = true\n\n# Rebind keys per action; each listed action loses its default keys. Press '{}' for the names.\n# [keys]\n# quit = [\"q\", \"Ctrl-q\"]\n# run_in_transaction = \"T\"\n\n# Colours: a preset (dark, light, high-contrast or no-color; no-color is the default when\n# NO_COLOR is set), then overrides per role as \"<fg>\", \"<fg> on <bg>\" or \"on <bg>\".\n# Roles: focus, selection, cursor, cursor_row, folder, dim, accent, popup, rename_popup,\n# confirm_popup, transaction, header, null, error, keyword, string, number, comment, parameter, identifier.\n# [theme]\n# preset = \"light\"\n# selection = \"white on #005f87\"\n# null = \"dark-gray\"\n\n# Pane proportions in percent.\n# [layout]\n# list_width = 20\n# preview_height = 40\n",
+                keymap.describe(Action::PickDatabase),
+                keymap.describe(Action::ToggleReadOnly),
+                keymap.describe(Action::Help)
+            ),
         )?;
     }
 
-    if !db_path.exists() {
+    // Only the built-in database is created on demand; configured files must already exist
+    let db_path = data_dir_path.join(DB_NAME);
+    if config.databases.is_empty() && !db_path.exists() {
         let conn = Connection::open(&db_path).expect("Failed to create dummy DB");
         conn.execute_batch("").expect("Failed to open dummy DB");
     }
 
+    let value_format = ValueFormat {
+        float_precision: config.float_precision,
+    };
+    let parameters_path = data_dir_path.join(PARAMETERS_FILE_NAME);
+    let history_path = data_dir_path.join(HISTORY_DB_NAME);
+    let read_only = options.read_only || config.read_only;
+
+    if !matches!(command, Command::Tui) {
+        let context = Context {
+            script_dir_path: &script_dir_path,
+            db_path: &databases[active_database].path,
+            databases: &databases,
+            parameters_path: &parameters_path,
+            history_path: &history_path,
+            value_format,
+            read_only,
+        };
+        return run_command(command, &context);
+    }
+
     enable_raw_mode()?;
     let mut stdout = stdout();
     execute!(stdout, EnterAlternateScreen, EnableMouseCapture)?;
     let backend = ratatui::backend::CrosstermBackend::new(stdout);
     let mut terminal = Terminal::new(backend)?;
 
-    let mut app = App::new(&script_dir_path, &db_path)?;
-    let res = run_app(&mut terminal, &mut app, &db_path, &script_dir_path);
+    let mut app = App::new(
+        &script_dir_path,
+        databases,
+        active_database,
+        &parameters_path,
+        &history_path,
+        value_format,
+        keymap,
+    )?;
+    app.read_only = read_only;
+    if !key_problems.is_empty() {
+        app.query_result
+            .push_str(&format!(" (Key bindings: {})", key_problems.join("; ")));
+    }
+    // NO_COLOR only counts when set to something, per no-color.org
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|value| !value.is_empty());
+    let (theme, mut theme_problems) = Theme::from_config(&config.theme, no_color);
+    let (layout, layout_problems) = config.layout.checked();
+    theme_problems.extend(layout_problems);
+    app.theme = theme;
+    app.layout = layout;
+    if !theme_problems.is_empty() {
+        app.query_result
+            .push_str(&format!(" (Theme: {})", theme_problems.join("; ")));
+    }
+    let res = run_app(&mut terminal, &mut app, &script_dir_path);
 
     disable_raw_mode()?;
     execute!(
@@ -78,84 +180,60 @@ fn main() -> io::Result<()> {
         println!("{:?}", err)
     }
 
-    Ok(())
+    Ok(ExitCode::SUCCESS)
 }
 
 /// The main TUI loop
 fn run_app<B: Backend + io::Write>(
     terminal: &mut Terminal<B>,
     app: &mut App,
-    db_path: &Path,
     script_dir_path: &Path,
 ) -> io::Result<()> {
     loop {
+        collect_finished_run(app);
+        if app
+            .script_watcher
+            .as_ref()
+            .is_some_and(|watcher| watcher.has_changes())
+        {
+            // The directory may be mid-rename by git or another tool; keep running either way
+            if let Err(e) = app.rescan_scripts(script_dir_path) {
+                app.query_result = e.to_string();
+            }
+        }
         terminal.draw(|f| ui(f, app))?;
 
+        // Wake up regularly so the run spinner ticks and finished runs are picked up
+        if !event::poll(TICK_RATE)? {
+            continue;
+        }
         if let Event::Key(key) = event::read()? {
             if key.kind == KeyEventKind::Press {
+                if let Some(running) = &mut app.running {
+                    // `back` only cancels from Normal mode; in a popup it closes the popup
+                    let back = app.input_mode == InputMode::Normal
+                        && app.keymap.action_for(&key) == Some(Action::Back);
+                    if back || app.keymap.is_bound(&key, Action::CancelRun) {
+                        running.cancel();
+                        continue;
+                    }
+                }
                 match app.input_mode {
-                    InputMode::Normal => match key.code {
-                        KeyCode::Char('q') => return Ok(()),
-                        KeyCode::Char('j') | KeyCode::Down => app.next(),
-                        KeyCode::Char('k') | KeyCode::Up => app.previous(),
-                        KeyCode::Char('l') | KeyCode::Enter => {
-                            execute_sql(app, &db_path.to_string_lossy())
-                        }
-                        KeyCode::Char('e') => {
-                            if let Some(selected_index) = app.list_state.selected() {
-                                if let Some(file_path_str) = app.sql_files.get(selected_index) {
-                                    let file_path = Path::new(file_path_str);
-                                    let success = open_editor(terminal, file_path)?;
-                                    if !success {
-                                        app.query_result =
-                                            "Editor exited with an error.".to_string();
-                                    }
-                                    app.rescan_scripts(script_dir_path)?;
-                                }
-                            }
-                        }
-                        KeyCode::Char('a') => {
-                            app.input_mode = InputMode::EditingFilename;
-                            app.filename_input.clear();
-                            app.query_result =
-                                "Enter new script name (no extension). Press [Enter] to confirm, [Esc] to cancel."
-                                    .to_string();
-                        }
-                        KeyCode::Char('d') => {
-                            if app.list_state.selected().is_some() {
-                                app.input_mode = InputMode::ConfirmingDelete;
-                                // ‼️ Use helper to get stem (from original code)
-                                let filename = app.get_selected_filename_stem().unwrap_or_default();
-                                app.query_result = format!("Delete '{}'? (y/n)", filename);
-                            } else {
-                                app.query_result = "No script selected to delete.".to_string();
+                    InputMode::Normal => {
+                        if let Some(action) = app.keymap.action_for(&key) {
+                            if perform_action(terminal, app, action, script_dir_path)? {
+                                return Ok(());
                             }
                         }
-                        KeyCode::Char('r') => {
-                            // ‼️ Use helper to get stem (from original code)
-                            if let Some(filename_stem) = app.get_selected_filename_stem() {
-                                app.input_mode = InputMode::RenamingScript;
-                                app.filename_input = filename_stem;
-                                app.query_result =
-                                    "Enter new script name (no extension). Press [Enter] to confirm, [Esc] to cancel."
-                                        .to_string();
-                            } else {
-                                app.query_result = "No script selected to rename.".to_string();
-                            }
-                        }
-                        KeyCode::Char('?') => {
-                            app.input_mode = InputMode::ShowHelp;
-                        }
-                        _ => {}
-                    },
-                    InputMode::EditingFilename => match key.code {
-                        KeyCode::Enter => {
+                    }
+                    InputMode::EditingFilename => match app.keymap.input_action_for(&key) {
+                        Some(Action::Select) => {
                             let filename_stem = app.filename_input.trim();
                             if filename_stem.is_empty() {
                                 app.input_mode = InputMode::Normal;
                                 app.query_result = "New script cancelled.".to_string();
                             } else {
-                                let mut new_file_path = script_dir_path.to_path_buf();
+                                let mut new_file_path = app.selected_folder();
                                 // ‼️ Add .sql extension manually (from original code)
                                 new_file_path.push(format!("{}.sql", filename_stem));
                                 if new_file_path.exists() {
@@ -166,7 +244,8 @@ fn run_app<B: Backend + io::Write>(
                                 } else {
                                     let new_file_path_str =
                                         new_file_path.to_string_lossy().to_string();
-                                    fs::write(&new_file_path, "")?;
+                                    fs::write(&new_file_path, &app.new_script_template)?;
+                                    app.left_pane = LeftPane::Scripts;
                                     let success = open_editor(terminal, &new_file_path)?;
                                     if !success {
                                         app.query_result =
@@ -178,41 +257,30 @@ fn run_app<B: Backend + io::Write>(
                                         );
                                     }
                                     app.rescan_scripts(script_dir_path)?;
-                                    if let Some(new_index) =
-                                        app.sql_files.iter().position(|p| p == &new_file_path_str)
-                                    {
-                                        app.list_state.select(Some(new_index));
-                                        app.update_preview();
-                                    }
+                                    app.select_path(&new_file_path_str);
                                 }
                                 app.input_mode = InputMode::Normal;
                             }
                         }
-                        KeyCode::Char('c')
-                            if key
-                                .modifiers
-                                .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                        {
+                        Some(Action::Back | Action::CancelRun) => {
                             app.input_mode = InputMode::Normal;
                             app.query_result = "New script cancelled.".to_string();
                         }
-                        KeyCode::Esc => {
-                            app.input_mode = InputMode::Normal;
-                            app.query_result = "New script cancelled.".to_string();
+                        _ => {
+                            edit_text(&mut app.filename_input, &key);
                         }
-                        KeyCode::Backspace => {
-                            app.filename_input.pop();
-                        }
-                        KeyCode::Char(c) => {
-                            app.filename_input.push(c);
-                        }
-                        _ => {}
                     },
                     InputMode::ConfirmingDelete => match key.code {
-                        KeyCode::Char('y') => {
+                        _ if app.keymap.is_bound(&key, Action::Confirm) => {
                             if let Some(selected_index) = app.list_state.selected() {
                                 if let Some(file_path_str) = app.sql_files.get(selected_index) {
-                                    match fs::remove_file(file_path_str) {
+                                    // Folders are only removed when empty, never recursively
+                                    let removed = if app.is_folder(file_path_str) {
+                                        fs::remove_dir(file_path_str)
+                                    } else {
+                                        fs::remove_file(file_path_str)
+                                    };
+                                    match removed {
                                         Ok(_) => {
                                             app.query_result =
                                                 format!("File {} deleted.", file_path_str);
@@ -229,14 +297,40 @@ fn run_app<B: Backend + io::Write>(
                             }
                             app.input_mode = InputMode::Normal;
                         }
-                        KeyCode::Char('n') | KeyCode::Esc => {
+                        _ if app.keymap.is_bound(&key, Action::Cancel) => {
                             app.input_mode = InputMode::Normal;
                             app.query_result = "Deletion cancelled.".to_string();
                         }
                         _ => {}
                     },
-                    InputMode::RenamingScript => match key.code {
-                        KeyCode::Enter => {
+                    InputMode::ReviewingTransaction => match key.code {
+                        _ if app.keymap.is_bound(&key, Action::Commit) => {
+                            end_transaction(app, true)
+                        }
+                        _ if app.keymap.is_bound(&key, Action::RollBack) => {
+                            end_transaction(app, false)
+                        }
+                        _ if app.keymap.is_bound(&key, Action::KeepTransactionOpen) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        // Dropping the connection rolls the transaction back
+                        _ if app.keymap.is_bound(&key, Action::QuitAndRollBack) => return Ok(()),
+                        _ => {}
+                    },
+                    InputMode::ConfirmingRun => match key.code {
+                        _ if app.keymap.is_bound(&key, Action::Confirm) => {
+                            app.input_mode = InputMode::Normal;
+                            run_confirmed(app);
+                        }
+                        _ if app.keymap.is_bound(&key, Action::Cancel) => {
+                            app.input_mode = InputMode::Normal;
+                            app.pending_run = None;
+                            app.query_result = "Run cancelled.".to_string();
+                        }
+                        _ => {}
+                    },
+                    InputMode::RenamingScript => match app.keymap.input_action_for(&key) {
+                        Some(Action::Select) => {
                             let new_filename_stem = app.filename_input.trim();
                             if new_filename_stem.is_empty() {
                                 app.input_mode = InputMode::Normal;
@@ -249,8 +343,12 @@ fn run_app<B: Backend + io::Write>(
                                             .parent()
                                             .unwrap_or(script_dir_path)
                                             .to_path_buf();
-                                        // ‼️ Add .sql extension manually (from original code)
-                                        new_path.push(format!("{}.sql", new_filename_stem));
+                                        if app.is_folder(old_path_str) {
+                                            new_path.push(new_filename_stem);
+                                        } else {
+                                            // ‼️ Add .sql extension manually (from original code)
+                                            new_path.push(format!("{}.sql", new_filename_stem));
+                                        }
 
                                         if new_path.exists() {
                                             app.query_result = format!(
@@ -264,14 +362,7 @@ fn run_app<B: Backend + io::Write>(
                                                     let new_path_str =
                                                         new_path.to_string_lossy().to_string();
                                                     app.rescan_scripts(script_dir_path)?;
-                                                    if let Some(new_index) = app
-                                                        .sql_files
-                                                        .iter()
-                                                        .position(|p| p == &new_path_str)
-                                                    {
-                                                        app.list_state.select(Some(new_index));
-                                                        app.update_preview();
-                                                    }
+                                                    app.select_path(&new_path_str);
                                                 }
                                                 Err(e) => {
                                                     app.query_result =
@@ -284,34 +375,368 @@ fn run_app<B: Backend + io::Write>(
                                 app.input_mode = InputMode::Normal;
                             }
                         }
-                        KeyCode::Char('c')
-                            if key
-                                .modifiers
-                                .contains(crossterm::event::KeyModifiers::CONTROL) =>
-                        {
+                        Some(Action::Back | Action::CancelRun) => {
                             app.input_mode = InputMode::Normal;
                             app.query_result = "Rename cancelled.".to_string();
                         }
-                        KeyCode::Esc => {
+                        _ => {
+                            edit_text(&mut app.filename_input, &key);
+                        }
+                    },
+                    InputMode::Inspecting => match app.keymap.action_for(&key) {
+                        Some(Action::Quit | Action::Back | Action::Select) => {
                             app.input_mode = InputMode::Normal;
-                            app.query_result = "Rename cancelled.".to_string();
                         }
-                        KeyCode::Backspace => {
-                            app.filename_input.pop();
+                        Some(Action::Down) => {
+                            app.inspector.scroll = app.inspector.scroll.saturating_add(1);
+                        }
+                        Some(Action::Up) => {
+                            app.inspector.scroll = app.inspector.scroll.saturating_sub(1);
+                        }
+                        Some(Action::PageDown) => {
+                            app.inspector.scroll = app.inspector.scroll.saturating_add(10);
+                        }
+                        Some(Action::PageUp) => {
+                            app.inspector.scroll = app.inspector.scroll.saturating_sub(10);
                         }
-                        KeyCode::Char(c) => {
-                            app.filename_input.push(c);
+                        Some(Action::SwitchFocus | Action::InspectRow) => {
+                            app.inspector.target = match app.inspector.target {
+                                InspectTarget::Cell => InspectTarget::Row,
+                                InspectTarget::Row => InspectTarget::Cell,
+                            };
+                            app.inspector.scroll = 0;
                         }
                         _ => {}
                     },
-                    InputMode::ShowHelp => match key.code {
-                        KeyCode::Char('q') | KeyCode::Esc | KeyCode::Char('?') => {
+                    InputMode::EnteringParameters => match app.keymap.input_action_for(&key) {
+                        Some(Action::Select) => {
                             app.input_mode = InputMode::Normal;
+                            execute_sql_with_parameters(app);
                         }
+                        Some(Action::Back | Action::CancelRun) => {
+                            app.input_mode = InputMode::Normal;
+                            app.query_result = "Run cancelled.".to_string();
+                        }
+                        Some(Action::SwitchFocus | Action::Down) => app.parameter_form.next_field(),
+                        Some(Action::Up) => app.parameter_form.previous_field(),
+                        _ => {
+                            if let Some(input) = app.parameter_form.selected_input() {
+                                edit_text(input, &key);
+                            }
+                        }
+                    },
+                    InputMode::Exporting => match app.keymap.input_action_for(&key) {
+                        Some(Action::Select) => {
+                            let path_str =
+                                shellexpand::tilde(app.filename_input.trim()).to_string();
+                            if path_str.is_empty() {
+                                app.query_result = "Export cancelled.".to_string();
+                            } else {
+                                let path = Path::new(&path_str);
+                                app.query_result = match app.export_current_result(path) {
+                                    Ok(rows) => format!(
+                                        "Exported {} rows as {} to {}.",
+                                        rows,
+                                        app.export_format.label(),
+                                        path.display()
+                                    ),
+                                    Err(e) => {
+                                        format!("Error exporting to {}: {}", path.display(), e)
+                                    }
+                                };
+                            }
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(Action::SwitchFocus) => {
+                            // Follow the format change only while the path is still the default one
+                            let default_path = app.default_export_path(script_dir_path);
+                            let is_default = app.filename_input == default_path.to_string_lossy();
+                            app.export_format = app.export_format.next_export_format();
+                            if is_default {
+                                app.filename_input = app
+                                    .default_export_path(script_dir_path)
+                                    .to_string_lossy()
+                                    .to_string();
+                            }
+                        }
+                        Some(Action::Back | Action::CancelRun) => {
+                            app.input_mode = InputMode::Normal;
+                            app.query_result = "Export cancelled.".to_string();
+                        }
+                        _ => {
+                            edit_text(&mut app.filename_input, &key);
+                        }
+                    },
+                    InputMode::BrowsingHistory => match app.keymap.action_for(&key) {
+                        Some(Action::Down) => app.next_history_entry(),
+                        Some(Action::Up) => app.previous_history_entry(),
+                        Some(Action::History | Action::Quit | Action::Back) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(Action::Select) => {
+                            // Open a copy so the ledger's SQL can't be edited in place
+                            if let Some((entry, sql)) = app.selected_history_sql() {
+                                let file_name =
+                                    format!("sqledger-run-{}.sql", &entry.sql_hash[..12]);
+                                let temp_path = std::env::temp_dir().join(file_name);
+                                fs::write(&temp_path, sql)?;
+                                let success = open_editor(terminal, &temp_path)?;
+                                if !success {
+                                    app.query_result = "Editor exited with an error.".to_string();
+                                }
+                            }
+                        }
+                        _ => {}
+                    },
+                    InputMode::CreatingFolder => match app.keymap.input_action_for(&key) {
+                        Some(Action::Select) => {
+                            let folder_name = app.filename_input.trim();
+                            if folder_name.is_empty() {
+                                app.query_result = "New folder cancelled.".to_string();
+                            } else {
+                                let new_folder_path = app.selected_folder().join(folder_name);
+                                match fs::create_dir(&new_folder_path) {
+                                    Ok(_) => {
+                                        app.query_result = format!(
+                                            "Folder {} created.",
+                                            new_folder_path.display()
+                                        );
+                                        app.rescan_scripts(script_dir_path)?;
+                                        app.select_path(&new_folder_path.to_string_lossy());
+                                    }
+                                    Err(e) => {
+                                        app.query_result = format!(
+                                            "Error creating folder {}: {}",
+                                            new_folder_path.display(),
+                                            e
+                                        );
+                                    }
+                                }
+                            }
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(Action::Back | Action::CancelRun) => {
+                            app.input_mode = InputMode::Normal;
+                            app.query_result = "New folder cancelled.".to_string();
+                        }
+                        _ => {
+                            edit_text(&mut app.filename_input, &key);
+                        }
+                    },
+                    InputMode::SearchingScripts => match app.keymap.input_action_for(&key) {
+                        Some(Action::Select) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(Action::Back | Action::CancelRun) => {
+                            app.input_mode = InputMode::Normal;
+                            app.clear_script_search();
+                        }
+                        Some(Action::SwitchFocus) => app.toggle_search_contents(),
+                        Some(Action::Down) => app.next(),
+                        Some(Action::Up) => app.previous(),
+                        _ => {
+                            if edit_text(&mut app.search_query, &key) {
+                                app.apply_script_search();
+                            }
+                        }
+                    },
+                    InputMode::FilteringColumn => match app.keymap.input_action_for(&key) {
+                        Some(Action::Select) => app.apply_column_filter(),
+                        Some(Action::Back | Action::CancelRun) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        _ => {
+                            edit_text(&mut app.filename_input, &key);
+                        }
+                    },
+                    InputMode::PickingDatabase => match app.keymap.action_for(&key) {
+                        Some(Action::Down) => app.next_database(),
+                        Some(Action::Up) => app.previous_database(),
+                        Some(Action::PickDatabase | Action::Quit | Action::Back) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        Some(Action::Select) => app.switch_to_selected_database(),
                         _ => {}
                     },
+                    InputMode::ShowHelp => match app.keymap.action_for(&key) {
+                        Some(Action::Down) => {
+                            app.help_scroll = app.help_scroll.saturating_add(1);
+                        }
+                        Some(Action::Up) => {
+                            app.help_scroll = app.help_scroll.saturating_sub(1);
+                        }
+                        Some(Action::Help | Action::Quit | Action::Back) => {
+                            app.input_mode = InputMode::Normal;
+                        }
+                        _ => {}
+                    },
+                }
+            }
+        }
+    }
+}
+
+/// Applies a key press that isn't an action to a text input: Backspace deletes and characters
+/// are typed. Returns whether the text changed.
+fn edit_text(input: &mut String, key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Backspace => input.pop().is_some(),
+        KeyCode::Char(c) => {
+            input.push(c);
+            true
+        }
+        _ => false,
+    }
+}
+
+/// Carries out a Normal-mode action on the focused pane. Returns `true` when the app should quit.
+fn perform_action<B: Backend + io::Write>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    action: Action,
+    script_dir_path: &Path,
+) -> io::Result<bool> {
+    let results = app.focus == Focus::Results;
+    let schema = app.left_pane == LeftPane::Schema;
+    match action {
+        Action::Quit if app.transaction.is_some() => app.warn_open_transaction(),
+        Action::Quit => return Ok(true),
+        Action::Help => {
+            app.help_scroll = 0;
+            app.input_mode = InputMode::ShowHelp;
+        }
+        Action::SwitchFocus => app.toggle_focus(),
+        Action::Back if results => app.toggle_focus(),
+        Action::Back if schema => app.toggle_left_pane(),
+        Action::Back if !app.search_query.is_empty() => app.clear_script_search(),
+        Action::Down if results => app.move_result_row(1),
+        Action::Down if schema => app.next_schema_object(),
+        Action::Down => app.next(),
+        Action::Up if results => app.move_result_row(-1),
+        Action::Up if schema => app.previous_schema_object(),
+        Action::Up => app.previous(),
+        Action::Left if results => app.move_result_column(-1),
+        Action::Left if !schema => app.collapse_selected_folder(),
+        Action::Right if results => app.move_result_column(1),
+        Action::Select if results => app.open_inspector(InspectTarget::Cell),
+        Action::Right | Action::Select if schema => app.open_table_browser(),
+        Action::Right | Action::Select if app.selected_script().is_none() => {
+            app.toggle_selected_folder();
+        }
+        Action::Right | Action::Select => execute_sql(app),
+        Action::PageDown if results => app.result_page_down(),
+        Action::PageDown => app.scroll_preview(true),
+        Action::PageUp if results => app.result_page_up(),
+        Action::PageUp => app.scroll_preview(false),
+        Action::FirstRow if results => app.first_result_row(),
+        Action::LastRow if results => app.last_result_row(),
+        Action::InspectRow if results => app.open_inspector(InspectTarget::Row),
+        Action::CopyCell if results => yank(terminal, app, YankTarget::Cell)?,
+        Action::CopyRow if results => yank(terminal, app, YankTarget::Row)?,
+        Action::CopyCsv if results => yank(terminal, app, YankTarget::ResultCsv)?,
+        Action::CopyMarkdown if results => yank(terminal, app, YankTarget::ResultMarkdown)?,
+        Action::Sort if results => app.cycle_browser_sort(),
+        Action::Filter if results => app.open_column_filter(),
+        Action::ClearFilters if results => app.clear_column_filters(),
+        Action::NextResult => app.next_result(),
+        Action::PreviousResult => app.previous_result(),
+        Action::ToggleBytecode => app.toggle_bytecode(),
+        Action::Export => start_export(app, script_dir_path),
+        Action::History if !results => app.open_history(),
+        Action::PickDatabase if !results => app.open_database_picker(),
+        Action::ToggleReadOnly if !results => app.toggle_read_only(),
+        Action::ToggleSchema if !results => app.toggle_left_pane(),
+        Action::NewScriptFromTable if schema && !results => app.new_script_from_schema(),
+        // The rest act on the script list
+        _ if results || schema => {}
+        Action::RunInTransaction => execute_sql_in_transaction(app),
+        Action::Explain => explain_sql(app),
+        Action::Search => app.start_script_search(),
+        Action::Edit => {
+            if let Some(file_path_str) = app.selected_script() {
+                let file_path = Path::new(file_path_str);
+                let success = open_editor(terminal, file_path)?;
+                if !success {
+                    app.query_result = "Editor exited with an error.".to_string();
                 }
+                app.rescan_scripts(script_dir_path)?;
+            }
+        }
+        Action::NewScript => {
+            app.input_mode = InputMode::EditingFilename;
+            app.filename_input.clear();
+            app.new_script_template.clear();
+            app.query_result = format!(
+                "Enter new script name (no extension). {}",
+                app.keymap.input_prompt("confirm")
+            );
+        }
+        Action::NewFolder => {
+            app.input_mode = InputMode::CreatingFolder;
+            app.filename_input.clear();
+            app.query_result = format!(
+                "Enter new folder name. {}",
+                app.keymap.input_prompt("confirm")
+            );
+        }
+        Action::Delete => {
+            if app.list_state.selected().is_some() {
+                app.input_mode = InputMode::ConfirmingDelete;
+                // ‼️ Use helper to get stem (from original code)
+                let filename = app.get_selected_filename_stem().unwrap_or_default();
+                app.query_result = format!(
+                    "Delete '{}'? ([{}] delete, [{}] cancel)",
+                    filename,
+                    app.keymap.describe(Action::Confirm),
+                    app.keymap.describe(Action::Cancel)
+                );
+            } else {
+                app.query_result = "No script selected to delete.".to_string();
+            }
+        }
+        Action::Rename => {
+            // ‼️ Use helper to get stem (from original code)
+            if let Some(filename_stem) = app.get_selected_filename_stem() {
+                app.input_mode = InputMode::RenamingScript;
+                app.filename_input = filename_stem;
+                app.query_result = format!(
+                    "Enter new script name (no extension). {}",
+                    app.keymap.input_prompt("confirm")
+                );
+            } else {
+                app.query_result = "No script selected to rename.".to_string();
             }
         }
+        _ => {}
+    }
+    Ok(false)
+}
+
+/// Copies part of the current result to the clipboard through the terminal
+fn yank<B: Backend + io::Write>(
+    terminal: &mut Terminal<B>,
+    app: &mut App,
+    target: YankTarget,
+) -> io::Result<()> {
+    match app.yank_text(target) {
+        Some((text, description)) => {
+            copy_to_clipboard(terminal.backend_mut(), &text)?;
+            app.query_result = format!("Copied {} to the clipboard.", description);
+        }
+        None => app.query_result = "Nothing to copy.".to_string(),
+    }
+    Ok(())
+}
+
+/// Opens the export popup with the default path for the current format
+fn start_export(app: &mut App, script_dir_path: &Path) {
+    if app.current_result_set().is_none() {
+        app.query_result = "No result set to export.".to_string();
+        return;
     }
+    app.filename_input = app
+        .default_export_path(script_dir_path)
+        .to_string_lossy()
+        .to_string();
+    app.input_mode = InputMode::Exporting;
 }
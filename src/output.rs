@@ -0,0 +1,394 @@
+use crate::{
+    db::ResultSet,
+    value::{render_inline, render_value, ValueFormat},
+};
+use rusqlite::types::Value;
+use std::io::{self, Write};
+
+/// Text formats a result set can be written in
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum OutputFormat {
+    Table,
+    Csv,
+    Tsv,
+    Json,
+    Ndjson,
+    Markdown,
+    Insert,
+}
+
+impl OutputFormat {
+    pub fn parse(name: &str) -> Option<Self> {
+        match name.to_lowercase().as_str() {
+            "table" => Some(OutputFormat::Table),
+            "csv" => Some(OutputFormat::Csv),
+            "tsv" => Some(OutputFormat::Tsv),
+            "json" => Some(OutputFormat::Json),
+            "ndjson" | "jsonl" => Some(OutputFormat::Ndjson),
+            "markdown" | "md" => Some(OutputFormat::Markdown),
+            "insert" | "sql" => Some(OutputFormat::Insert),
+            _ => None,
+        }
+    }
+
+    /// File extension used when exporting in this format
+    pub fn extension(self) -> &'static str {
+        match self {
+            OutputFormat::Table => "txt",
+            OutputFormat::Csv => "csv",
+            OutputFormat::Tsv => "tsv",
+            OutputFormat::Json => "json",
+            OutputFormat::Ndjson => "ndjson",
+            OutputFormat::Markdown => "md",
+            OutputFormat::Insert => "sql",
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            OutputFormat::Table => "Table",
+            OutputFormat::Csv => "CSV",
+            OutputFormat::Tsv => "TSV",
+            OutputFormat::Json => "JSON",
+            OutputFormat::Ndjson => "NDJSON",
+            OutputFormat::Markdown => "Markdown",
+            OutputFormat::Insert => "SQL INSERT",
+        }
+    }
+
+    /// The next format offered by the export popup
+    pub fn next_export_format(self) -> Self {
+        match self {
+            OutputFormat::Csv => OutputFormat::Json,
+            OutputFormat::Json => OutputFormat::Markdown,
+            OutputFormat::Markdown => OutputFormat::Insert,
+            _ => OutputFormat::Csv,
+        }
+    }
+}
+
+/// Writes one result set in the given format; `table_name` is only used by INSERT output
+pub fn write_result_set<W: Write>(
+    out: &mut W,
+    result_set: &ResultSet,
+    format: OutputFormat,
+    table_name: &str,
+    value_format: &ValueFormat,
+) -> io::Result<()> {
+    match format {
+        OutputFormat::Table => write_table(out, result_set, value_format),
+        OutputFormat::Csv => write_csv(out, result_set),
+        OutputFormat::Tsv => write_tsv(out, result_set),
+        OutputFormat::Json => {
+            let json = serde_json::to_string_pretty(&result_set_to_json(result_set))
+                .map_err(io::Error::other)?;
+            writeln!(out, "{}", json)
+        }
+        OutputFormat::Ndjson => {
+            let keys = json_keys(&result_set.columns);
+            for row in &result_set.rows {
+                let json =
+                    serde_json::to_string(&row_to_json(&keys, row)).map_err(io::Error::other)?;
+                writeln!(out, "{}", json)?;
+            }
+            Ok(())
+        }
+        OutputFormat::Markdown => write_markdown(out, result_set, value_format),
+        OutputFormat::Insert => write_inserts(out, result_set, table_name),
+    }
+}
+
+/// Writes the result sets of a script in the given format, keeping the whole output valid.
+///
+/// JSON is always one array: the rows of a single result set, or for any other count an array
+/// per result set (`[]` when there are none). NDJSON rows simply follow each other; other
+/// formats get a blank line between blocks.
+pub fn write_result_sets<W: Write>(
+    out: &mut W,
+    result_sets: &[&ResultSet],
+    format: OutputFormat,
+    table_name: &str,
+    value_format: &ValueFormat,
+) -> io::Result<()> {
+    if format == OutputFormat::Json && result_sets.len() != 1 {
+        let statements: Vec<serde_json::Value> = result_sets
+            .iter()
+            .map(|result_set| result_set_to_json(result_set))
+            .collect();
+        let json = serde_json::to_string_pretty(&statements).map_err(io::Error::other)?;
+        return writeln!(out, "{}", json);
+    }
+    for (i, result_set) in result_sets.iter().enumerate() {
+        if i > 0 && format != OutputFormat::Ndjson {
+            writeln!(out)?;
+        }
+        write_result_set(out, result_set, format, table_name, value_format)?;
+    }
+    Ok(())
+}
+
+/// Aligned, pipe-separated columns with a dashed rule under the header
+fn write_table<W: Write>(
+    out: &mut W,
+    result_set: &ResultSet,
+    value_format: &ValueFormat,
+) -> io::Result<()> {
+    let rows: Vec<Vec<String>> = result_set
+        .rows
+        .iter()
+        .map(|row| {
+            row.iter()
+                .map(|value| render_inline(value.into(), value_format))
+                .collect()
+        })
+        .collect();
+    let mut widths: Vec<usize> = result_set
+        .columns
+        .iter()
+        .map(|name| name.chars().count())
+        .collect();
+    for row in &rows {
+        for (width, value) in widths.iter_mut().zip(row) {
+            *width = (*width).max(value.chars().count());
+        }
+    }
+
+    let header: Vec<String> = result_set
+        .columns
+        .iter()
+        .zip(&widths)
+        .map(|(name, width)| format!("{:<width$}", name, width = width))
+        .collect();
+    writeln!(out, "{}", header.join(" | "))?;
+    let rule: Vec<String> = widths.iter().map(|width| "-".repeat(*width)).collect();
+    writeln!(out, "{}", rule.join("-+-"))?;
+    for row in &rows {
+        let cells: Vec<String> = row
+            .iter()
+            .zip(&widths)
+            .map(|(value, width)| format!("{:<width$}", value, width = width))
+            .collect();
+        writeln!(out, "{}", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+/// An array with one object per row
+fn result_set_to_json(result_set: &ResultSet) -> serde_json::Value {
+    let keys = json_keys(&result_set.columns);
+    result_set
+        .rows
+        .iter()
+        .map(|row| row_to_json(&keys, row))
+        .collect()
+}
+
+/// RFC 4180 CSV: CRLF line endings, fields quoted when they contain `,`, `"` or line breaks
+fn write_csv<W: Write>(out: &mut W, result_set: &ResultSet) -> io::Result<()> {
+    let header: Vec<String> = result_set.columns.iter().map(|c| csv_field(c)).collect();
+    write!(out, "{}\r\n", header.join(","))?;
+    for row in &result_set.rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|value| csv_field(&plain_text(value)))
+            .collect();
+        write!(out, "{}\r\n", fields.join(","))?;
+    }
+    Ok(())
+}
+
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
+
+/// Tab-separated values, with tabs, line breaks and backslashes escaped
+fn write_tsv<W: Write>(out: &mut W, result_set: &ResultSet) -> io::Result<()> {
+    let header: Vec<String> = result_set.columns.iter().map(|c| tsv_field(c)).collect();
+    writeln!(out, "{}", header.join("\t"))?;
+    for row in &result_set.rows {
+        writeln!(out, "{}", tsv_row(row))?;
+    }
+    Ok(())
+}
+
+/// Formats a single row as a tab-separated line
+pub fn tsv_row(row: &[Value]) -> String {
+    let fields: Vec<String> = row
+        .iter()
+        .map(|value| tsv_field(&plain_text(value)))
+        .collect();
+    fields.join("\t")
+}
+
+fn tsv_field(field: &str) -> String {
+    field
+        .replace('\\', "\\\\")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+/// A GitHub-flavoured Markdown table
+fn write_markdown<W: Write>(
+    out: &mut W,
+    result_set: &ResultSet,
+    value_format: &ValueFormat,
+) -> io::Result<()> {
+    let header: Vec<String> = result_set
+        .columns
+        .iter()
+        .map(|c| markdown_cell(c))
+        .collect();
+    writeln!(out, "| {} |", header.join(" | "))?;
+    let rule: Vec<&str> = result_set.columns.iter().map(|_| "---").collect();
+    writeln!(out, "| {} |", rule.join(" | "))?;
+    for row in &result_set.rows {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|value| markdown_cell(&render_value(value.into(), value_format)))
+            .collect();
+        writeln!(out, "| {} |", cells.join(" | "))?;
+    }
+    Ok(())
+}
+
+fn markdown_cell(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace('\n', "<br>")
+}
+
+/// One `INSERT INTO` statement per row, with every value written as an SQL literal
+fn write_inserts<W: Write>(
+    out: &mut W,
+    result_set: &ResultSet,
+    table_name: &str,
+) -> io::Result<()> {
+    let columns: Vec<String> = result_set
+        .columns
+        .iter()
+        .map(|c| quote_identifier(c))
+        .collect();
+    let prefix = format!(
+        "INSERT INTO {} ({}) VALUES",
+        quote_identifier(table_name),
+        columns.join(", ")
+    );
+    for row in &result_set.rows {
+        let literals: Vec<String> = row.iter().map(sql_literal).collect();
+        writeln!(out, "{} ({});", prefix, literals.join(", "))?;
+    }
+    Ok(())
+}
+
+pub fn quote_identifier(name: &str) -> String {
+    format!("\"{}\"", name.replace('"', "\"\""))
+}
+
+/// Writes a value back as SQL source that evaluates to the same value and type
+pub fn sql_literal(value: &Value) -> String {
+    match value {
+        Value::Null => "NULL".to_string(),
+        Value::Integer(i) => i.to_string(),
+        Value::Real(f) if f.is_nan() => "NULL".to_string(),
+        Value::Real(f) if f.is_infinite() => {
+            // SQLite reads out-of-range literals as +/-Inf
+            if *f > 0.0 { "9e999" } else { "-9e999" }.to_string()
+        }
+        Value::Real(f) => format!("{:?}", f),
+        Value::Text(text) => format!("'{}'", text.replace('\'', "''")),
+        Value::Blob(bytes) => format!("X'{}'", hex_string(bytes)),
+    }
+}
+
+/// Text for delimited formats: NULL is an empty field and blobs are written as hex.
+/// REALs always use the shortest exact form, whatever `float_precision` is set to for display.
+pub fn plain_text(value: &Value) -> String {
+    match value {
+        Value::Null => String::new(),
+        Value::Blob(bytes) => hex_string(bytes),
+        _ => render_value(value.into(), &ValueFormat::default()),
+    }
+}
+
+/// Object keys for a row: column names, with repeats suffixed `:1`, `:2`, … so that
+/// `SELECT a.id, b.id` keeps both values
+fn json_keys(columns: &[String]) -> Vec<String> {
+    let mut keys: Vec<String> = Vec::with_capacity(columns.len());
+    for name in columns {
+        let mut key = name.clone();
+        let mut suffix = 0;
+        while keys.contains(&key) || (suffix > 0 && columns.contains(&key)) {
+            suffix += 1;
+            key = format!("{}:{}", name, suffix);
+        }
+        keys.push(key);
+    }
+    keys
+}
+
+/// Builds a JSON object for one row; blobs become hex strings and non-finite reals null
+fn row_to_json(keys: &[String], row: &[Value]) -> serde_json::Value {
+    let object = keys
+        .iter()
+        .zip(row)
+        .map(|(name, value)| {
+            let json = match value {
+                Value::Null => serde_json::Value::Null,
+                Value::Integer(i) => serde_json::Value::from(*i),
+                Value::Real(f) => serde_json::Number::from_f64(*f)
+                    .map(serde_json::Value::Number)
+                    .unwrap_or(serde_json::Value::Null),
+                Value::Text(text) => serde_json::Value::String(text.clone()),
+                Value::Blob(bytes) => serde_json::Value::String(hex_string(bytes)),
+            };
+            (name.clone(), json)
+        })
+        .collect();
+    serde_json::Value::Object(object)
+}
+
+fn hex_string(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn repeated_column_names_keep_every_value() {
+        let result_set = ResultSet {
+            columns: vec!["id".to_string(), "id".to_string(), "id:1".to_string()],
+            rows: vec![vec![
+                Value::Integer(1),
+                Value::Integer(2),
+                Value::Integer(3),
+            ]],
+        };
+        assert_eq!(
+            result_set_to_json(&result_set),
+            serde_json::json!([{ "id": 1, "id:2": 2, "id:1": 3 }])
+        );
+    }
+
+    #[test]
+    fn delimited_formats_ignore_display_precision() {
+        let result_set = ResultSet {
+            columns: vec!["x".to_string()],
+            rows: vec![vec![Value::Real(1.23456)]],
+        };
+        let value_format = ValueFormat {
+            float_precision: Some(1),
+        };
+        let mut out = Vec::new();
+        write_result_set(&mut out, &result_set, OutputFormat::Csv, "", &value_format).unwrap();
+        assert_eq!(String::from_utf8(out).unwrap(), "x\r\n1.23456\r\n");
+        assert_eq!(tsv_row(&result_set.rows[0]), "1.23456");
+    }
+}
@@ -0,0 +1,105 @@
+use crate::lexer::{tokenize, TokenKind};
+use rusqlite::types::Value;
+use std::{collections::HashMap, fs, io, path::Path};
+
+/// Last parameter values entered for each script, keyed by script path and then parameter name
+pub type SavedParameters = HashMap<String, HashMap<String, String>>;
+
+/// Numbers the parameters in one statement the way SQLite does, as `(spelling, index)` pairs.
+///
+/// A `?` takes the index after the largest one so far, `?NNN` takes NNN, and a name keeps
+/// the index it got when it first appeared. Indexes no parameter takes are left out, so gaps
+/// in `?NNN` numbering don't turn into parameters.
+pub fn number_parameters(sql: &str) -> Vec<(String, usize)> {
+    let mut numbered: Vec<(String, usize)> = Vec::new();
+    let mut largest = 0;
+    for token in tokenize(sql) {
+        if token.kind != TokenKind::Parameter {
+            continue;
+        }
+        let earlier = numbered.iter().find(|(text, _)| text == token.text);
+        let index = match (token.text, earlier) {
+            ("?", _) => largest + 1,
+            (_, Some(&(_, index))) => index,
+            (text, None) => text
+                .strip_prefix('?')
+                .and_then(|digits| digits.parse().ok())
+                .unwrap_or(largest + 1),
+        };
+        largest = largest.max(index);
+        numbered.push((token.text.to_string(), index));
+    }
+    numbered
+}
+
+/// Turns text typed into the parameter form into a typed SQLite value.
+///
+/// Numbers are bound as INTEGER or REAL and `NULL` (any case) as NULL. Anything else is TEXT,
+/// including numbers with leading zeros such as `00123`, `nan` and `inf`, and any value in
+/// single quotes: `'42'` binds the text `42`, with `''` standing for a quote inside it.
+pub fn parse_parameter_value(input: &str) -> Value {
+    if let Some(quoted) = input
+        .strip_prefix('\'')
+        .and_then(|rest| rest.strip_suffix('\''))
+    {
+        Value::Text(quoted.replace("''", "'"))
+    } else if input.eq_ignore_ascii_case("null") {
+        Value::Null
+    } else if !is_plain_number(input) {
+        Value::Text(input.to_string())
+    } else if let Ok(i) = input.parse::<i64>() {
+        Value::Integer(i)
+    } else if let Some(f) = input.parse::<f64>().ok().filter(|f| f.is_finite()) {
+        Value::Real(f)
+    } else {
+        Value::Text(input.to_string())
+    }
+}
+
+/// Whether `input` starts like a number and has no leading zeros that binding it as one would lose
+fn is_plain_number(input: &str) -> bool {
+    let unsigned = input.strip_prefix(['-', '+']).unwrap_or(input);
+    let mut chars = unsigned.chars();
+    match (chars.next(), chars.next()) {
+        (Some('0'), Some(next)) => !next.is_ascii_digit(),
+        (Some(first), _) => first.is_ascii_digit() || first == '.',
+        (None, _) => false,
+    }
+}
+
+pub fn load_saved_parameters(path: &Path) -> SavedParameters {
+    fs::read_to_string(path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+pub fn save_parameters(path: &Path, saved: &SavedParameters) -> io::Result<()> {
+    let content = serde_json::to_string_pretty(saved).map_err(io::Error::other)?;
+    fs::write(path, content)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn values_that_would_lose_their_spelling_stay_text() {
+        assert_eq!(parse_parameter_value("123"), Value::Integer(123));
+        assert_eq!(parse_parameter_value("-0.5"), Value::Real(-0.5));
+        assert_eq!(parse_parameter_value("0"), Value::Integer(0));
+        assert_eq!(parse_parameter_value("null"), Value::Null);
+        for text in ["00123", "-007", "nan", "inf", "1e999", "abc"] {
+            assert_eq!(parse_parameter_value(text), Value::Text(text.to_string()));
+        }
+        assert_eq!(parse_parameter_value("'42'"), Value::Text("42".to_string()));
+        assert_eq!(
+            parse_parameter_value("'NULL'"),
+            Value::Text("NULL".to_string())
+        );
+        assert_eq!(
+            parse_parameter_value("'it''s'"),
+            Value::Text("it's".to_string())
+        );
+    }
+}
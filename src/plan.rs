@@ -0,0 +1,132 @@
+use crate::{
+    db::{collect_rows, ResultSet},
+    lexer::{tokenize, TokenKind},
+};
+use rusqlite::{Connection, Result as SqlResult};
+
+/// One row of `EXPLAIN QUERY PLAN`
+#[derive(Clone, Debug)]
+pub struct PlanStep {
+    pub id: i64,
+    /// The `id` of the step this one belongs under; 0 for top-level steps
+    pub parent: i64,
+    pub detail: String,
+}
+
+/// How SQLite would run a statement: the query plan and the raw `EXPLAIN` bytecode
+#[derive(Clone, Debug, Default)]
+pub struct QueryPlan {
+    pub steps: Vec<PlanStep>,
+    pub bytecode: ResultSet,
+}
+
+impl QueryPlan {
+    /// Each step with the tree-drawing prefix that places it under its parent
+    pub fn tree(&self) -> Vec<(String, &PlanStep)> {
+        let mut lines = Vec::with_capacity(self.steps.len());
+        // Per open ancestor, whether more siblings follow it and so its rail continues
+        let mut rails: Vec<(i64, bool)> = Vec::new();
+        for (i, step) in self.steps.iter().enumerate() {
+            while rails.last().is_some_and(|&(id, _)| id != step.parent) {
+                rails.pop();
+            }
+            let has_next_sibling = self.steps[i + 1..]
+                .iter()
+                .any(|later| later.parent == step.parent);
+            let mut prefix: String = rails
+                .iter()
+                .map(|&(_, continues)| if continues { "│  " } else { "   " })
+                .collect();
+            prefix.push_str(if has_next_sibling {
+                "├─ "
+            } else {
+                "└─ "
+            });
+            lines.push((prefix, step));
+            rails.push((step.id, has_next_sibling));
+        }
+        lines
+    }
+
+    /// Number of steps that read a whole table
+    pub fn full_scans(&self) -> usize {
+        self.steps
+            .iter()
+            .filter(|step| is_full_scan(&step.detail))
+            .count()
+    }
+}
+
+/// A `SCAN` step that walks every row of a table rather than searching an index
+pub fn is_full_scan(detail: &str) -> bool {
+    detail.starts_with("SCAN ") && !detail.contains(" USING ") && detail != "SCAN CONSTANT ROW"
+}
+
+/// Whether a step sorts or groups in a temporary b-tree, often a sign of a missing index
+pub fn uses_temp_btree(detail: &str) -> bool {
+    detail.starts_with("USE TEMP B-TREE")
+}
+
+/// Explains one statement without running it. A statement that is already an `EXPLAIN` or
+/// `EXPLAIN QUERY PLAN` is explained as the statement it wraps.
+pub fn explain_statement(conn: &Connection, sql: &str) -> SqlResult<QueryPlan> {
+    let sql = strip_explain(sql);
+    let mut stmt = conn.prepare(&format!("EXPLAIN QUERY PLAN {}", sql))?;
+    // Parameters are left unbound: they don't change the plan
+    let mut rows = stmt.raw_query();
+    let mut steps = Vec::new();
+    while let Some(row) = rows.next()? {
+        steps.push(PlanStep {
+            id: row.get(0)?,
+            parent: row.get(1)?,
+            detail: row.get(3)?,
+        });
+    }
+    let bytecode = collect_rows(&mut conn.prepare(&format!("EXPLAIN {}", sql))?)?;
+    Ok(QueryPlan { steps, bytecode })
+}
+
+/// `sql` without a leading `EXPLAIN` or `EXPLAIN QUERY PLAN`
+fn strip_explain(sql: &str) -> &str {
+    // The first three words, each with the offset just past it
+    let mut words = Vec::new();
+    let mut offset = 0;
+    for token in tokenize(sql) {
+        offset += token.text.len();
+        if !matches!(token.kind, TokenKind::Whitespace | TokenKind::Comment) {
+            words.push((token.text.to_ascii_uppercase(), offset));
+            if words.len() == 3 {
+                break;
+            }
+        }
+    }
+    match words.as_slice() {
+        [(explain, _), (query, _), (plan, end)]
+            if explain == "EXPLAIN" && query == "QUERY" && plan == "PLAN" =>
+        {
+            &sql[*end..]
+        }
+        [(explain, end), ..] if explain == "EXPLAIN" => &sql[*end..],
+        _ => sql,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explain_statements_are_explained_once() {
+        let conn = Connection::open_in_memory().unwrap();
+        conn.execute_batch("CREATE TABLE t(x)").unwrap();
+        for sql in [
+            "SELECT * FROM t",
+            "EXPLAIN SELECT * FROM t",
+            "explain query plan SELECT * FROM t",
+        ] {
+            let plan = explain_statement(&conn, sql).unwrap();
+            assert_eq!(plan.full_scans(), 1, "{}", sql);
+        }
+        assert_eq!(strip_explain("EXPLAIN QUERY SELECT 1"), " QUERY SELECT 1");
+    }
+}
@@ -0,0 +1,245 @@
+use crate::output::quote_identifier;
+use rusqlite::{Connection, Result as SqlResult};
+use std::fmt::Write;
+
+/// A table, view, index or trigger read from `sqlite_schema`
+#[derive(Clone, Debug)]
+pub struct SchemaObject {
+    /// `table`, `view`, `index` or `trigger`
+    pub kind: String,
+    pub name: String,
+    /// The table an index or trigger belongs to; the object itself for tables and views
+    pub table_name: String,
+    /// The `CREATE` statement; `None` for indexes SQLite creates for constraints
+    pub sql: Option<String>,
+    pub columns: Vec<ColumnInfo>,
+    pub indexes: Vec<IndexInfo>,
+    pub foreign_keys: Vec<ForeignKey>,
+}
+
+/// One row of `pragma_table_info`
+#[derive(Clone, Debug)]
+pub struct ColumnInfo {
+    pub name: String,
+    pub declared_type: String,
+    pub not_null: bool,
+    pub default_value: Option<String>,
+    /// 1-based position in the primary key, or 0 if not part of it
+    pub primary_key: i64,
+}
+
+/// One row of `pragma_index_list`
+#[derive(Clone, Debug)]
+pub struct IndexInfo {
+    pub name: String,
+    pub unique: bool,
+    /// `c` for CREATE INDEX, `u` for a UNIQUE constraint, `pk` for the primary key
+    pub origin: String,
+    pub partial: bool,
+}
+
+/// One row of `pragma_foreign_key_list`
+#[derive(Clone, Debug)]
+pub struct ForeignKey {
+    pub from: String,
+    pub table: String,
+    /// `None` when the key references the parent's primary key implicitly
+    pub to: Option<String>,
+    pub on_update: String,
+    pub on_delete: String,
+}
+
+impl SchemaObject {
+    /// Whether rows can be selected from this object
+    pub fn is_relation(&self) -> bool {
+        self.kind == "table" || self.kind == "view"
+    }
+
+    /// One-letter tag shown before the name in the schema list
+    pub fn tag(&self) -> &'static str {
+        match self.kind.as_str() {
+            "table" => "T",
+            "view" => "V",
+            "index" => "I",
+            "trigger" => "R",
+            _ => "?",
+        }
+    }
+
+    /// A readable summary of columns, indexes, foreign keys and the DDL
+    pub fn describe(&self) -> String {
+        let mut text = format!("{} {}", self.kind, self.name);
+        if self.table_name != self.name {
+            let _ = write!(text, " on {}", self.table_name);
+        }
+        text.push('\n');
+
+        if !self.columns.is_empty() {
+            text.push_str("\nColumns:\n");
+            let name_width = self
+                .columns
+                .iter()
+                .map(|c| c.name.chars().count())
+                .max()
+                .unwrap_or(0);
+            let type_width = self
+                .columns
+                .iter()
+                .map(|c| c.declared_type.chars().count())
+                .max()
+                .unwrap_or(0);
+            for column in &self.columns {
+                let mut constraints = Vec::new();
+                if column.primary_key > 0 {
+                    constraints.push("PRIMARY KEY".to_string());
+                }
+                if column.not_null {
+                    constraints.push("NOT NULL".to_string());
+                }
+                if let Some(default_value) = &column.default_value {
+                    constraints.push(format!("DEFAULT {}", default_value));
+                }
+                let line = format!(
+                    "  {:<name_width$}  {:<type_width$}  {}",
+                    column.name,
+                    column.declared_type,
+                    constraints.join(" "),
+                    name_width = name_width,
+                    type_width = type_width
+                );
+                text.push_str(line.trim_end());
+                text.push('\n');
+            }
+        }
+
+        if !self.indexes.is_empty() {
+            text.push_str("\nIndexes:\n");
+            for index in &self.indexes {
+                let mut notes = vec![match index.origin.as_str() {
+                    "pk" => "primary key",
+                    "u" => "unique constraint",
+                    _ => "created",
+                }];
+                if index.unique && index.origin == "c" {
+                    notes.push("unique");
+                }
+                if index.partial {
+                    notes.push("partial");
+                }
+                let _ = writeln!(text, "  {} ({})", index.name, notes.join(", "));
+            }
+        }
+
+        if !self.foreign_keys.is_empty() {
+            text.push_str("\nForeign keys:\n");
+            for key in &self.foreign_keys {
+                let _ = writeln!(
+                    text,
+                    "  {} -> {}({}) ON UPDATE {} ON DELETE {}",
+                    key.from,
+                    key.table,
+                    key.to.as_deref().unwrap_or("<primary key>"),
+                    key.on_update,
+                    key.on_delete
+                );
+            }
+        }
+
+        if let Some(sql) = &self.sql {
+            let _ = write!(text, "\nDDL:\n{};\n", sql);
+        }
+        text
+    }
+}
+
+/// Reads every schema object, ordered tables, views, indexes, triggers and then by name
+pub fn load_schema(conn: &Connection) -> SqlResult<Vec<SchemaObject>> {
+    let mut stmt = conn.prepare(
+        "SELECT type, name, tbl_name, sql FROM sqlite_schema
+         ORDER BY CASE type WHEN 'table' THEN 0 WHEN 'view' THEN 1 WHEN 'index' THEN 2 ELSE 3 END,
+                  name",
+    )?;
+    let mut objects = stmt
+        .query_map([], |row| {
+            Ok(SchemaObject {
+                kind: row.get(0)?,
+                name: row.get(1)?,
+                table_name: row.get(2)?,
+                sql: row.get(3)?,
+                columns: Vec::new(),
+                indexes: Vec::new(),
+                foreign_keys: Vec::new(),
+            })
+        })?
+        .collect::<SqlResult<Vec<_>>>()?;
+    for object in &mut objects {
+        if object.is_relation() {
+            // A view over a dropped table can't be described, but should still be listed
+            object.columns = table_info(conn, &object.name).unwrap_or_default();
+        }
+        if object.kind == "table" {
+            object.indexes = index_list(conn, &object.name)?;
+            object.foreign_keys = foreign_key_list(conn, &object.name)?;
+        }
+    }
+    Ok(objects)
+}
+
+pub fn table_info(conn: &Connection, table: &str) -> SqlResult<Vec<ColumnInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, type, \"notnull\", dflt_value, pk FROM pragma_table_info(?1) ORDER BY cid",
+    )?;
+    let columns = stmt
+        .query_map([table], |row| {
+            Ok(ColumnInfo {
+                name: row.get(0)?,
+                declared_type: row.get(1)?,
+                not_null: row.get(2)?,
+                default_value: row.get(3)?,
+                primary_key: row.get(4)?,
+            })
+        })?
+        .collect();
+    columns
+}
+
+fn index_list(conn: &Connection, table: &str) -> SqlResult<Vec<IndexInfo>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, \"unique\", origin, partial FROM pragma_index_list(?1) ORDER BY seq",
+    )?;
+    let indexes = stmt
+        .query_map([table], |row| {
+            Ok(IndexInfo {
+                name: row.get(0)?,
+                unique: row.get(1)?,
+                origin: row.get(2)?,
+                partial: row.get(3)?,
+            })
+        })?
+        .collect();
+    indexes
+}
+
+fn foreign_key_list(conn: &Connection, table: &str) -> SqlResult<Vec<ForeignKey>> {
+    let mut stmt = conn.prepare(
+        "SELECT \"from\", \"table\", \"to\", on_update, on_delete
+         FROM pragma_foreign_key_list(?1) ORDER BY id, seq",
+    )?;
+    let keys = stmt
+        .query_map([table], |row| {
+            Ok(ForeignKey {
+                from: row.get(0)?,
+                table: row.get(1)?,
+                to: row.get(2)?,
+                on_update: row.get(3)?,
+                on_delete: row.get(4)?,
+            })
+        })?
+        .collect();
+    keys
+}
+
+/// The starter query written into a new script from the schema browser
+pub fn select_template(table: &str) -> String {
+    format!("SELECT * FROM {} LIMIT 100;\n", quote_identifier(table))
+}
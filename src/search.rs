@@ -0,0 +1,54 @@
+/// A successful fuzzy match: higher scores are better matches
+#[derive(Clone, Debug, PartialEq)]
+pub struct FuzzyMatch {
+    pub score: i64,
+    /// Char indices in the text that matched the pattern, in order
+    pub positions: Vec<usize>,
+}
+
+/// Matches `pattern` as a case-insensitive subsequence of `text`.
+///
+/// Consecutive matches and matches at the start of a word score higher, and gaps cost a
+/// little, so `usrrep` ranks `user_report` above `unused_strings_report`.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    let pattern: Vec<char> = pattern.chars().flat_map(char::to_lowercase).collect();
+    if pattern.is_empty() {
+        return Some(FuzzyMatch {
+            score: 0,
+            positions: Vec::new(),
+        });
+    }
+    let text: Vec<char> = text.chars().collect();
+    let mut positions: Vec<usize> = Vec::with_capacity(pattern.len());
+    let mut score = 0;
+    let mut next = 0;
+    for (i, c) in text.iter().enumerate() {
+        if next == pattern.len() {
+            break;
+        }
+        if !c.to_lowercase().eq(std::iter::once(pattern[next])) {
+            continue;
+        }
+        let word_start = i == 0 || !text[i - 1].is_alphanumeric();
+        let consecutive = positions.last().is_some_and(|&last| last + 1 == i);
+        score += 1;
+        if word_start {
+            score += 8;
+        }
+        if consecutive {
+            score += 5;
+        } else if let Some(&last) = positions.last() {
+            score -= (i - last - 1).min(5) as i64;
+        }
+        positions.push(i);
+        next += 1;
+    }
+    (next == pattern.len()).then_some(FuzzyMatch { score, positions })
+}
+
+/// The best match of `pattern` within any single line of `text`
+pub fn fuzzy_match_lines(pattern: &str, text: &str) -> Option<FuzzyMatch> {
+    text.lines()
+        .filter_map(|line| fuzzy_match(pattern, line))
+        .max_by_key(|m| m.score)
+}
@@ -0,0 +1,326 @@
+use ratatui::style::{Color, Modifier, Style};
+use serde::Deserialize;
+use std::{collections::BTreeMap, str::FromStr};
+
+/// Names accepted for `preset` under `[theme]`
+pub const PRESETS: [&str; 4] = ["dark", "light", "high-contrast", "no-color"];
+
+/// The `[theme]` section: a preset, then per-role colours such as `selection = "black on green"`
+#[derive(Deserialize, Debug, Default)]
+pub struct ThemeConfig {
+    pub preset: Option<String>,
+    #[serde(flatten)]
+    pub colors: BTreeMap<String, String>,
+}
+
+/// The styles the UI draws with, one per role
+#[derive(Clone, Debug)]
+pub struct Theme {
+    /// Border of the focused pane
+    pub focus: Style,
+    /// Selected item in the script and schema lists
+    pub selection: Style,
+    /// The cursor cell in the results grid, and the selected entry in the history and
+    /// database popups
+    pub cursor: Style,
+    /// The rest of the cursor's row in the results grid
+    pub cursor_row: Style,
+    pub folder: Style,
+    /// Secondary text: line numbers, schema tags, inactive tabs, the stats footer
+    pub dim: Style,
+    /// Search input, the read-only marker, match highlights and counters worth a look
+    pub accent: Style,
+    /// Input popups: new script or folder, parameters, export, filter
+    pub popup: Style,
+    pub rename_popup: Style,
+    /// Popups confirming a delete or a modifying run
+    pub confirm_popup: Style,
+    /// The open-transaction banner and popup
+    pub transaction: Style,
+    /// Column headers of result tables
+    pub header: Style,
+    pub null: Style,
+    /// Failed runs, unknown databases and full table scans
+    pub error: Style,
+    pub keyword: Style,
+    pub string: Style,
+    pub number: Style,
+    pub comment: Style,
+    pub parameter: Style,
+    pub identifier: Style,
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::dark()
+    }
+}
+
+impl Theme {
+    /// Built for dark terminal backgrounds; the original colours
+    pub fn dark() -> Self {
+        let fg = |color| Style::default().fg(color);
+        Self {
+            focus: fg(Color::LightGreen),
+            selection: Style::default()
+                .bg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            cursor: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD),
+            cursor_row: Style::default().bg(Color::DarkGray),
+            folder: fg(Color::Blue),
+            dim: fg(Color::DarkGray),
+            accent: fg(Color::Yellow),
+            popup: Style::default().bg(Color::LightBlue),
+            rename_popup: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            confirm_popup: Style::default().fg(Color::White).bg(Color::Red),
+            transaction: Style::default().fg(Color::Black).bg(Color::Yellow),
+            header: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            null: fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            error: fg(Color::Red),
+            keyword: fg(Color::Magenta).add_modifier(Modifier::BOLD),
+            string: fg(Color::Green),
+            number: fg(Color::Cyan),
+            comment: fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            parameter: fg(Color::Yellow).add_modifier(Modifier::BOLD),
+            identifier: fg(Color::Blue),
+        }
+    }
+
+    /// Avoids the pale yellows and greens that vanish on white backgrounds
+    pub fn light() -> Self {
+        let fg = |color| Style::default().fg(color);
+        Self {
+            focus: fg(Color::Blue).add_modifier(Modifier::BOLD),
+            selection: Style::default()
+                .fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            cursor: Style::default()
+                .fg(Color::White)
+                .bg(Color::Blue)
+                .add_modifier(Modifier::BOLD),
+            cursor_row: Style::default().bg(Color::Gray),
+            folder: fg(Color::Blue),
+            dim: fg(Color::DarkGray),
+            accent: fg(Color::Magenta),
+            popup: Style::default().fg(Color::Black).bg(Color::LightCyan),
+            rename_popup: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            confirm_popup: Style::default().fg(Color::White).bg(Color::Red),
+            transaction: Style::default().fg(Color::Black).bg(Color::LightYellow),
+            header: Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            null: fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            error: fg(Color::Red),
+            keyword: fg(Color::Blue).add_modifier(Modifier::BOLD),
+            string: fg(Color::Green),
+            number: fg(Color::Magenta),
+            comment: fg(Color::DarkGray).add_modifier(Modifier::ITALIC),
+            parameter: fg(Color::Red).add_modifier(Modifier::BOLD),
+            identifier: Style::default(),
+        }
+    }
+
+    /// Bright colours and bold text throughout
+    pub fn high_contrast() -> Self {
+        let fg = |color| Style::default().fg(color);
+        let bold = Modifier::BOLD;
+        Self {
+            focus: fg(Color::LightYellow).add_modifier(bold),
+            selection: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(bold),
+            cursor: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(bold),
+            cursor_row: Style::default().fg(Color::Black).bg(Color::White),
+            folder: fg(Color::LightCyan).add_modifier(bold),
+            dim: fg(Color::Gray),
+            accent: fg(Color::LightYellow).add_modifier(bold),
+            popup: Style::default().fg(Color::Black).bg(Color::White),
+            rename_popup: Style::default().fg(Color::Black).bg(Color::White),
+            confirm_popup: Style::default()
+                .fg(Color::White)
+                .bg(Color::Red)
+                .add_modifier(bold),
+            transaction: Style::default()
+                .fg(Color::Black)
+                .bg(Color::LightYellow)
+                .add_modifier(bold),
+            header: fg(Color::LightYellow).add_modifier(bold | Modifier::UNDERLINED),
+            null: fg(Color::LightMagenta).add_modifier(Modifier::ITALIC),
+            error: fg(Color::LightRed).add_modifier(bold),
+            keyword: fg(Color::LightCyan).add_modifier(bold),
+            string: fg(Color::LightGreen),
+            number: fg(Color::LightMagenta),
+            comment: fg(Color::Gray).add_modifier(Modifier::ITALIC),
+            parameter: fg(Color::LightYellow).add_modifier(bold),
+            identifier: fg(Color::White),
+        }
+    }
+
+    /// No colours at all; selections and markers are shown with reverse video, bold and italics
+    pub fn no_color() -> Self {
+        let plain = Style::default();
+        let bold = plain.add_modifier(Modifier::BOLD);
+        let italic = plain.add_modifier(Modifier::ITALIC);
+        Self {
+            focus: bold,
+            selection: plain.add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            cursor: plain.add_modifier(Modifier::REVERSED | Modifier::BOLD),
+            cursor_row: bold,
+            folder: bold,
+            dim: plain,
+            accent: bold,
+            popup: plain,
+            rename_popup: plain,
+            confirm_popup: bold,
+            transaction: plain.add_modifier(Modifier::REVERSED),
+            header: plain.add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+            null: italic,
+            error: bold,
+            keyword: bold,
+            string: plain,
+            number: plain,
+            comment: italic,
+            parameter: bold,
+            identifier: plain,
+        }
+    }
+
+    pub fn preset(name: &str) -> Option<Self> {
+        match name {
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "high-contrast" => Some(Self::high_contrast()),
+            "no-color" => Some(Self::no_color()),
+            _ => None,
+        }
+    }
+
+    /// Builds the theme from `[theme]`. Without a `preset`, `no_color` (the `NO_COLOR`
+    /// convention) picks the colourless preset; colours set in the config still apply on top.
+    /// Unknown presets, roles and colours are skipped and reported, like `[keys]`.
+    pub fn from_config(config: &ThemeConfig, no_color: bool) -> (Self, Vec<String>) {
+        let mut problems = Vec::new();
+        let default_preset = if no_color { "no-color" } else { "dark" };
+        let preset = config.preset.as_deref().unwrap_or(default_preset);
+        let mut theme = Self::preset(preset).unwrap_or_else(|| {
+            problems.push(format!(
+                "Unknown theme preset '{}', expected one of {}",
+                preset,
+                PRESETS.join(", ")
+            ));
+            Self::preset(default_preset).unwrap_or_default()
+        });
+        for (role, value) in &config.colors {
+            let Some(style) = theme.role_mut(role) else {
+                problems.push(format!("Unknown role '{}' in [theme]", role));
+                continue;
+            };
+            match parse_colors(value) {
+                Ok((fg, bg)) => {
+                    if let Some(fg) = fg {
+                        *style = style.fg(fg);
+                    }
+                    if let Some(bg) = bg {
+                        *style = style.bg(bg);
+                    }
+                }
+                Err(e) => problems.push(e),
+            }
+        }
+        (theme, problems)
+    }
+
+    fn role_mut(&mut self, role: &str) -> Option<&mut Style> {
+        Some(match role {
+            "focus" => &mut self.focus,
+            "selection" => &mut self.selection,
+            "cursor" => &mut self.cursor,
+            "cursor_row" => &mut self.cursor_row,
+            "folder" => &mut self.folder,
+            "dim" => &mut self.dim,
+            "accent" => &mut self.accent,
+            "popup" => &mut self.popup,
+            "rename_popup" => &mut self.rename_popup,
+            "confirm_popup" => &mut self.confirm_popup,
+            "transaction" => &mut self.transaction,
+            "header" => &mut self.header,
+            "null" => &mut self.null,
+            "error" => &mut self.error,
+            "keyword" => &mut self.keyword,
+            "string" => &mut self.string,
+            "number" => &mut self.number,
+            "comment" => &mut self.comment,
+            "parameter" => &mut self.parameter,
+            "identifier" => &mut self.identifier,
+            _ => return None,
+        })
+    }
+}
+
+/// Parses `"<fg>"`, `"<fg> on <bg>"` or `"on <bg>"`, where each colour is a name such as
+/// `light-green`, a 256-colour index or `#rrggbb`
+fn parse_colors(value: &str) -> Result<(Option<Color>, Option<Color>), String> {
+    let words: Vec<&str> = value.split_whitespace().collect();
+    let (fg, bg) = match words
+        .iter()
+        .position(|word| word.eq_ignore_ascii_case("on"))
+    {
+        Some(i) => (&words[..i], Some(&words[i + 1..])),
+        None => (&words[..], None),
+    };
+    let parse = |words: &[&str]| {
+        let text = words.join(" ");
+        Color::from_str(&text).map_err(|_| format!("Unknown colour '{}' in [theme]", text))
+    };
+    let fg = if fg.is_empty() {
+        None
+    } else {
+        Some(parse(fg)?)
+    };
+    let bg = bg.map(parse).transpose()?;
+    Ok((fg, bg))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn dark_preset_keeps_the_original_styles() {
+        let theme = Theme::dark();
+        assert_eq!(
+            theme.selection,
+            Style::default()
+                .bg(Color::LightGreen)
+                .add_modifier(Modifier::BOLD)
+        );
+        assert_eq!(
+            theme.rename_popup,
+            Style::default().fg(Color::Black).bg(Color::LightYellow)
+        );
+    }
+
+    #[test]
+    fn roles_take_colours_from_the_config() {
+        let config = ThemeConfig {
+            preset: None,
+            colors: BTreeMap::from([
+                ("cursor".to_string(), "white on #005f87".to_string()),
+                ("rename_popup".to_string(), "on blue".to_string()),
+                ("nope".to_string(), "red".to_string()),
+            ]),
+        };
+        let (theme, problems) = Theme::from_config(&config, true);
+        assert_eq!(problems, ["Unknown role 'nope' in [theme]"]);
+        assert_eq!(theme.cursor.fg, Some(Color::White));
+        assert_eq!(theme.cursor.bg, Some(Color::Rgb(0, 0x5f, 0x87)));
+        assert_eq!(theme.rename_popup.bg, Some(Color::Blue));
+        assert_eq!(theme.selection.bg, None);
+    }
+}
@@ -1,58 +1,103 @@
-use crate::app::{App, InputMode};
+use crate::{
+    app::{App, Focus, InputMode, InspectTarget, LeftPane, ResultView},
+    db::{ResultSet, StatementOutput, StatementStats},
+    keys::Action,
+    lexer::{tokenize, TokenKind},
+    plan::{is_full_scan, uses_temp_btree, QueryPlan},
+    theme::Theme,
+    value::{render_detail, render_inline, ValueFormat},
+};
 use ratatui::{
     layout::{Alignment, Constraint, Direction, Layout, Rect},
-    style::{Color, Modifier, Style},
-    widgets::{Block, Borders, Clear, List, ListItem, Paragraph},
+    style::{Modifier, Style},
+    text::{Line, Span},
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, Paragraph, Row, Table, TableState, Wrap,
+    },
     Frame,
 };
-use std::{ffi::OsStr, path::Path};
+use rusqlite::types::Value;
+use std::{ffi::OsStr, path::Path, time::Duration};
+
+const COLUMN_SPACING: u16 = 1;
+const MIN_COLUMN_WIDTH: u16 = 8;
+const MAX_STATUS_LINES: usize = 3;
+const SPINNER_FRAMES: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
 
 /// Renders the user interface
 pub fn ui(f: &mut Frame, app: &mut App) {
     let chunks = Layout::default()
         .direction(Direction::Horizontal)
-        .constraints([Constraint::Percentage(20), Constraint::Percentage(80)].as_ref())
+        .constraints(
+            [
+                Constraint::Percentage(app.layout.list_width),
+                Constraint::Percentage(100 - app.layout.list_width),
+            ]
+            .as_ref(),
+        )
         .split(f.area());
 
-    // --- Left Pane: SQL File List ---
-    let items: Vec<ListItem> = app
-        .sql_files
-        .iter()
-        .map(|full_path| {
-            let filename_stem = Path::new(full_path)
-                .file_stem()
-                .unwrap_or_else(|| OsStr::new("invalid_filename"))
-                .to_string_lossy()
-                .to_string();
-            ListItem::new(filename_stem)
-        })
-        .collect();
-
-    let list = List::new(items)
-        .block(Block::default().borders(Borders::ALL).title("SQL Scripts"))
-        .highlight_style(
-            Style::default()
-                .bg(Color::LightGreen)
-                .add_modifier(Modifier::BOLD),
-        )
-        .highlight_symbol(">> ");
-    f.render_stateful_widget(list, chunks[0], &mut app.list_state);
+    // --- Left Pane: SQL File List or Schema Browser ---
+    let theme = app.theme.clone();
+    let mut left_block = Block::default()
+        .borders(Borders::ALL)
+        .title(left_pane_tabs(app.left_pane, &theme))
+        .border_style(focus_style(app.focus == Focus::Scripts, &theme));
+    let searching = app.input_mode == InputMode::SearchingScripts;
+    if app.left_pane == LeftPane::Scripts && (searching || !app.search_query.is_empty()) {
+        let cursor = if searching { "_" } else { "" };
+        let scope = if app.search_contents { "/+" } else { "/" };
+        left_block = left_block.title_bottom(Line::styled(
+            format!("{}{}{}", scope, app.search_query, cursor),
+            theme.accent,
+        ));
+    } else {
+        let mut spans = vec![Span::raw(format!("db: {}", app.active_database().name))];
+        if app.read_only {
+            spans.push(Span::styled(
+                " [ro]",
+                theme.accent.add_modifier(Modifier::BOLD),
+            ));
+        }
+        left_block = left_block.title_bottom(Line::from(spans));
+    }
+    if app.left_pane == LeftPane::Schema {
+        let items: Vec<ListItem> = app
+            .schema
+            .iter()
+            .map(|object| {
+                ListItem::new(Line::from(vec![
+                    Span::styled(format!("{} ", object.tag()), theme.dim),
+                    Span::raw(object.name.clone()),
+                ]))
+            })
+            .collect();
+        let list = List::new(items)
+            .block(left_block)
+            .highlight_style(theme.selection)
+            .highlight_symbol(">> ");
+        f.render_stateful_widget(list, chunks[0], &mut app.schema_state);
+    } else {
+        render_script_list(f, app, left_block, chunks[0]);
+    }
 
     // --- Right Panes (Vertically Split) ---
     let right_chunks = Layout::default()
         .direction(Direction::Vertical)
-        .constraints([Constraint::Percentage(40), Constraint::Percentage(60)].as_ref())
+        .constraints(
+            [
+                Constraint::Percentage(app.layout.preview_height),
+                Constraint::Percentage(100 - app.layout.preview_height),
+            ]
+            .as_ref(),
+        )
         .split(chunks[1]);
 
-    // Top-Right Pane: Script Preview
-    let preview_block = Block::default().borders(Borders::ALL).title("Preview");
-    let preview_text = Paragraph::new(app.script_content_preview.as_str()).block(preview_block);
-    f.render_widget(preview_text, right_chunks[0]);
+    // Top-Right Pane: Script Preview, or the selected object's structure in the schema tab
+    render_preview(f, app, right_chunks[0]);
 
     // Bottom-Right Pane: Query Results
-    let results_block = Block::default().borders(Borders::ALL).title("Results");
-    let results_text = Paragraph::new(app.query_result.as_str()).block(results_block);
-    f.render_widget(results_text, right_chunks[1]);
+    render_results(f, app, right_chunks[1]);
 
     // --- Popup Windows ---
     match app.input_mode {
@@ -62,7 +107,18 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             let popup_block = Block::default()
                 .title("New Script Name")
                 .borders(Borders::ALL)
-                .style(Style::default().bg(Color::LightBlue));
+                .style(theme.popup);
+            let input_paragraph = Paragraph::new(input_text.as_str()).block(popup_block);
+            f.render_widget(Clear, area);
+            f.render_widget(input_paragraph, area);
+        }
+        InputMode::CreatingFolder => {
+            let area = centered_rect(50, 3, f.area());
+            let input_text = format!("{}_", app.filename_input);
+            let popup_block = Block::default()
+                .title("New Folder Name")
+                .borders(Borders::ALL)
+                .style(theme.popup);
             let input_paragraph = Paragraph::new(input_text.as_str()).block(popup_block);
             f.render_widget(Clear, area);
             f.render_widget(input_paragraph, area);
@@ -72,41 +128,905 @@ pub fn ui(f: &mut Frame, app: &mut App) {
             let popup_block = Block::default()
                 .title("Confirm Deletion")
                 .borders(Borders::ALL)
-                .style(Style::default().bg(Color::Red).fg(Color::White));
+                .style(theme.confirm_popup);
             let popup_paragraph = Paragraph::new(app.query_result.as_str())
                 .block(popup_block)
                 .alignment(Alignment::Center);
             f.render_widget(Clear, area);
             f.render_widget(popup_paragraph, area);
         }
+        InputMode::ConfirmingRun => {
+            if let Some(pending) = &app.pending_run {
+                // One line per statement, capped so a long migration doesn't outgrow the screen
+                const MAX_LISTED: usize = 10;
+                let script = Path::new(&pending.script_path)
+                    .file_stem()
+                    .map(|stem| stem.to_string_lossy())
+                    .unwrap_or_default();
+                let mut lines = Vec::new();
+                let ends_transaction = &pending.modifications.ends_transaction;
+                if !ends_transaction.is_empty() {
+                    lines.push(Line::raw(format!(
+                        "{} would end the open transaction before you can review it:",
+                        script
+                    )));
+                    for statement in ends_transaction.iter().take(MAX_LISTED) {
+                        lines.push(Line::raw(format!("  {}", statement)));
+                    }
+                }
+                let statements = &pending.modifications.statements;
+                if !statements.is_empty() || pending.modifications.unchecked.is_some() {
+                    lines.push(Line::raw(format!("{} may modify the database:", script)));
+                }
+                for statement in statements.iter().take(MAX_LISTED) {
+                    lines.push(Line::raw(format!("  {}", statement)));
+                }
+                if statements.len() > MAX_LISTED {
+                    lines.push(Line::raw(format!(
+                        "  ... and {} more",
+                        statements.len() - MAX_LISTED
+                    )));
+                }
+                if let Some(reason) = &pending.modifications.unchecked {
+                    lines.push(Line::raw(format!(
+                        "The rest of the script can't be checked: {}",
+                        reason
+                    )));
+                }
+                let area = centered_rect(70, lines.len() as u16 + 2, f.area());
+                let popup_block = Block::default()
+                    .title("Confirm Run")
+                    .title_bottom(format!(
+                        "[{}] run  [{}] cancel",
+                        app.keymap.describe(Action::Confirm),
+                        app.keymap.describe(Action::Cancel)
+                    ))
+                    .borders(Borders::ALL)
+                    .style(theme.confirm_popup);
+                let popup_paragraph = Paragraph::new(lines).block(popup_block);
+                f.render_widget(Clear, area);
+                f.render_widget(popup_paragraph, area);
+            }
+        }
+        InputMode::ReviewingTransaction => {
+            if let Some(transaction) = &app.transaction {
+                let scripts: Vec<String> = transaction
+                    .scripts
+                    .iter()
+                    .map(|path| {
+                        Path::new(path)
+                            .file_stem()
+                            .map(|stem| stem.to_string_lossy().to_string())
+                            .unwrap_or_default()
+                    })
+                    .collect();
+                let changes: usize = app
+                    .results
+                    .iter()
+                    .map(|result| match result.output {
+                        StatementOutput::Changes(changes) => changes,
+                        _ => 0,
+                    })
+                    .sum();
+                let text = format!(
+                    "Open on '{}' for {}s. Scripts run: {}\nThe last run changed {} row(s); its results are shown below.",
+                    transaction.database.name,
+                    transaction.started.elapsed().as_secs(),
+                    if scripts.is_empty() {
+                        "none".to_string()
+                    } else {
+                        scripts.join(", ")
+                    },
+                    changes
+                );
+                // Over the preview, so the results of the run stay visible
+                let area = centered_rect(90, 4, right_chunks[0]);
+                let popup_block = Block::default()
+                    .title("Transaction")
+                    .title_bottom(format!(
+                        "[{}] commit  [{}] roll back  [{}] keep open  [{}] quit",
+                        app.keymap.describe(Action::Commit),
+                        app.keymap.describe(Action::RollBack),
+                        app.keymap.describe(Action::KeepTransactionOpen),
+                        app.keymap.describe(Action::QuitAndRollBack)
+                    ))
+                    .borders(Borders::ALL)
+                    .style(theme.transaction);
+                let popup_paragraph = Paragraph::new(text)
+                    .block(popup_block)
+                    .wrap(Wrap { trim: true });
+                f.render_widget(Clear, area);
+                f.render_widget(popup_paragraph, area);
+            }
+        }
         InputMode::RenamingScript => {
             let area = centered_rect(50, 3, f.area());
             let input_text = format!("{}_", app.filename_input);
             let popup_block = Block::default()
                 .title("Rename Script")
                 .borders(Borders::ALL)
-                .style(Style::default().bg(Color::LightYellow).fg(Color::Black));
+                .style(theme.rename_popup);
             let input_paragraph = Paragraph::new(input_text.as_str()).block(popup_block);
             f.render_widget(Clear, area);
             f.render_widget(input_paragraph, area);
         }
         InputMode::ShowHelp => {
-            let area = centered_rect(60, 15, f.area()); // 60% width, 15 lines height
-            let popup_block = Block::default().title("Help").borders(Borders::ALL);
+            // 70% width, tall enough for every help line plus the borders if the screen is
+            let lines = app.help_message.lines().count() as u16;
+            let height = (lines + 2).min(f.area().height);
+            app.help_scroll = app
+                .help_scroll
+                .min(lines.saturating_sub(height.saturating_sub(2)));
+            let area = centered_rect(70, height, f.area());
+            let mut popup_block = Block::default().title("Help").borders(Borders::ALL);
+            if lines + 2 > height {
+                popup_block = popup_block.title_bottom(format!(
+                    "[{}/{}] scroll  [{}] close",
+                    app.keymap.first_key(Action::Down),
+                    app.keymap.first_key(Action::Up),
+                    app.keymap.first_key(Action::Back)
+                ));
+            }
 
             let popup_paragraph = Paragraph::new(app.help_message.as_str())
                 .block(popup_block)
-                .alignment(Alignment::Left);
+                .alignment(Alignment::Left)
+                .scroll((app.help_scroll, 0));
 
             f.render_widget(Clear, area);
             f.render_widget(popup_paragraph, area);
         }
-        InputMode::Normal => {
+        InputMode::Inspecting => {
+            let height = f.area().height.saturating_mul(8) / 10;
+            let area = centered_rect(80, height, f.area());
+            let (title, content) = inspector_content(app);
+            let popup_block = Block::default()
+                .title(title)
+                .title_bottom(format!(
+                    "[{}] cell/row  [{}/{}] scroll  [{}] close",
+                    app.keymap.first_key(Action::SwitchFocus),
+                    app.keymap.first_key(Action::Down),
+                    app.keymap.first_key(Action::Up),
+                    app.keymap.first_key(Action::Back)
+                ))
+                .borders(Borders::ALL);
+            let popup_paragraph = Paragraph::new(content)
+                .block(popup_block)
+                .wrap(Wrap { trim: false })
+                .scroll((app.inspector.scroll, 0));
+            f.render_widget(Clear, area);
+            f.render_widget(popup_paragraph, area);
+        }
+        InputMode::EnteringParameters => {
+            let form = &app.parameter_form;
+            let height = form.fields.len() as u16 + 2;
+            let area = centered_rect(60, height, f.area());
+            let name_width = form
+                .fields
+                .iter()
+                .map(|(name, _)| name.chars().count())
+                .max()
+                .unwrap_or(0);
+            let lines: Vec<Line> = form
+                .fields
+                .iter()
+                .enumerate()
+                .map(|(index, (name, value))| {
+                    if index == form.selected {
+                        Line::styled(
+                            format!("{:>width$}: {}_", name, value, width = name_width),
+                            Style::default().add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        Line::raw(format!("{:>width$}: {}", name, value, width = name_width))
+                    }
+                })
+                .collect();
+            let title = Path::new(&form.script_path)
+                .file_stem()
+                .map(|stem| format!("Parameters for {}", stem.to_string_lossy()))
+                .unwrap_or_else(|| "Parameters".to_string());
+            let popup_block = Block::default()
+                .title(title)
+                .title_bottom(format!(
+                    "[{}] next  [{}] run  [{}] cancel",
+                    app.keymap.first_input_key(Action::SwitchFocus),
+                    app.keymap.first_input_key(Action::Select),
+                    app.keymap.first_input_key(Action::Back)
+                ))
+                .borders(Borders::ALL)
+                .style(theme.popup);
+            let popup_paragraph = Paragraph::new(lines).block(popup_block);
+            f.render_widget(Clear, area);
+            f.render_widget(popup_paragraph, area);
+        }
+        InputMode::Exporting => {
+            let area = centered_rect(70, 4, f.area());
+            let lines = vec![
+                Line::raw(format!("Format: {}", app.export_format.label())),
+                Line::raw(format!("{}_", app.filename_input)),
+            ];
+            let popup_block = Block::default()
+                .title("Export Result")
+                .title_bottom(format!(
+                    "[{}] format  [{}] export  [{}] cancel",
+                    app.keymap.first_input_key(Action::SwitchFocus),
+                    app.keymap.first_input_key(Action::Select),
+                    app.keymap.first_input_key(Action::Back)
+                ))
+                .borders(Borders::ALL)
+                .style(theme.popup);
+            let popup_paragraph = Paragraph::new(lines).block(popup_block);
+            f.render_widget(Clear, area);
+            f.render_widget(popup_paragraph, area);
+        }
+        InputMode::FilteringColumn => {
+            let area = centered_rect(60, 4, f.area());
+            let column = app
+                .table_browser
+                .as_ref()
+                .and_then(|browser| browser.columns.get(app.result_view.column))
+                .cloned()
+                .unwrap_or_default();
+            let lines = vec![
+                Line::styled("e.g. > 10, != x, ~ %abc%, NULL, !NULL", theme.dim),
+                Line::raw(format!("{}_", app.filename_input)),
+            ];
+            let popup_block = Block::default()
+                .title(format!("Filter {}", column))
+                .title_bottom(format!(
+                    "[{}] apply (empty clears)  [{}] cancel",
+                    app.keymap.first_input_key(Action::Select),
+                    app.keymap.first_input_key(Action::Back)
+                ))
+                .borders(Borders::ALL)
+                .style(theme.popup);
+            let popup_paragraph = Paragraph::new(lines).block(popup_block);
+            f.render_widget(Clear, area);
+            f.render_widget(popup_paragraph, area);
+        }
+        InputMode::BrowsingHistory => {
+            let height = f.area().height.saturating_mul(8) / 10;
+            let area = centered_rect(90, height, f.area());
+            let header = Row::new([
+                "Started", "Script", "Duration", "Returned", "Affected", "Status",
+            ])
+            .style(theme.header);
+            let rows = app.history_entries.iter().map(|entry| {
+                let script = Path::new(&entry.script_path)
+                    .file_stem()
+                    .unwrap_or_else(|| OsStr::new("invalid_filename"))
+                    .to_string_lossy()
+                    .to_string();
+                let status = match &entry.error {
+                    Some(e) => Cell::from(e.clone()).style(theme.error),
+                    None => Cell::from("ok"),
+                };
+                Row::new([
+                    Cell::from(entry.started_at.clone()),
+                    Cell::from(script),
+                    Cell::from(format!("{} ms", entry.duration_ms)),
+                    Cell::from(entry.rows_returned.to_string()),
+                    Cell::from(entry.rows_affected.to_string()),
+                    status,
+                ])
+            });
+            let table = Table::new(
+                rows,
+                [
+                    Constraint::Length(19),
+                    Constraint::Percentage(25),
+                    Constraint::Length(10),
+                    Constraint::Length(8),
+                    Constraint::Length(8),
+                    Constraint::Fill(1),
+                ],
+            )
+            .header(header)
+            .block(
+                Block::default()
+                    .title("Run History")
+                    .title_bottom(format!(
+                        "[{}/{}] move  [{}] open SQL  [{}] close",
+                        app.keymap.first_key(Action::Down),
+                        app.keymap.first_key(Action::Up),
+                        app.keymap.first_key(Action::Select),
+                        app.keymap.first_key(Action::Back)
+                    ))
+                    .borders(Borders::ALL),
+            )
+            .row_highlight_style(theme.cursor);
+            f.render_widget(Clear, area);
+            f.render_stateful_widget(table, area, &mut app.history_state);
+        }
+        InputMode::PickingDatabase => {
+            let height = app.databases.len() as u16 + 2;
+            let area = centered_rect(60, height, f.area());
+            let items: Vec<ListItem> = app
+                .databases
+                .iter()
+                .enumerate()
+                .map(|(i, database)| {
+                    let marker = if i == app.active_database { "*" } else { " " };
+                    ListItem::new(format!(
+                        "{} {}  {}",
+                        marker,
+                        database.name,
+                        database.path.display()
+                    ))
+                })
+                .collect();
+            let list = List::new(items)
+                .block(
+                    Block::default()
+                        .title("Switch Database")
+                        .title_bottom(format!(
+                            "[{}/{}] move  [{}] use  [{}] cancel",
+                            app.keymap.first_key(Action::Down),
+                            app.keymap.first_key(Action::Up),
+                            app.keymap.first_key(Action::Select),
+                            app.keymap.first_key(Action::Back)
+                        ))
+                        .borders(Borders::ALL),
+                )
+                .highlight_style(theme.cursor);
+            f.render_widget(Clear, area);
+            f.render_stateful_widget(list, area, &mut app.database_state);
+        }
+        InputMode::Normal | InputMode::SearchingScripts => {
             // Do nothing
         }
     }
 }
 
+/// Renders the Preview pane: the selected script with line numbers and SQL highlighting,
+/// or plain text for folders and schema objects, scrolled to `preview_scroll`
+fn render_preview(f: &mut Frame, app: &mut App, area: Rect) {
+    let lines: Vec<Line> = if app.left_pane == LeftPane::Schema {
+        app.selected_schema_object()
+            .map(|object| object.describe())
+            .unwrap_or_else(|| "No schema objects found.".to_string())
+            .lines()
+            .map(|line| Line::raw(line.to_string()))
+            .collect()
+    } else if app.selected_script().is_some() {
+        highlight_sql(&app.script_content_preview, &app.theme)
+    } else {
+        app.script_content_preview
+            .lines()
+            .map(|line| Line::raw(line.to_string()))
+            .collect()
+    };
+
+    let height = area.height.saturating_sub(2) as usize;
+    app.preview_height = height;
+    app.preview_scroll = app.preview_scroll.min(lines.len().saturating_sub(height));
+    let title = if lines.len() > height {
+        format!(
+            "Preview ({}-{} of {})",
+            app.preview_scroll + 1,
+            (app.preview_scroll + height).min(lines.len()),
+            lines.len()
+        )
+    } else {
+        "Preview".to_string()
+    };
+    let visible: Vec<Line> = lines
+        .into_iter()
+        .skip(app.preview_scroll)
+        .take(height)
+        .collect();
+    let preview_block = Block::default().borders(Borders::ALL).title(title);
+    f.render_widget(Paragraph::new(visible).block(preview_block), area);
+}
+
+/// Splits a script into highlighted lines, each prefixed with its line number
+fn highlight_sql(sql: &str, theme: &Theme) -> Vec<Line<'static>> {
+    let line_count = sql.lines().count().max(1);
+    let gutter_width = line_count.to_string().len();
+    let gutter = |number: usize| {
+        Span::styled(
+            format!("{:>width$} │ ", number, width = gutter_width),
+            theme.dim,
+        )
+    };
+
+    let mut lines = Vec::new();
+    let mut spans = vec![gutter(1)];
+    for token in tokenize(sql) {
+        let style = token_style(token.kind, theme);
+        // Strings and block comments can span lines, so every token is split at line breaks
+        for (i, piece) in token.text.split('\n').enumerate() {
+            if i > 0 {
+                lines.push(Line::from(std::mem::take(&mut spans)));
+                spans.push(gutter(lines.len() + 1));
+            }
+            let piece = piece.strip_suffix('\r').unwrap_or(piece);
+            if !piece.is_empty() {
+                spans.push(Span::styled(piece.replace('\t', "    "), style));
+            }
+        }
+    }
+    // A trailing newline ends the last line rather than starting an empty one
+    if !sql.ends_with('\n') || sql.is_empty() {
+        lines.push(Line::from(spans));
+    }
+    lines
+}
+
+fn token_style(kind: TokenKind, theme: &Theme) -> Style {
+    match kind {
+        TokenKind::Keyword => theme.keyword,
+        TokenKind::String => theme.string,
+        TokenKind::Number => theme.number,
+        TokenKind::Comment => theme.comment,
+        TokenKind::Parameter => theme.parameter,
+        TokenKind::Identifier => theme.identifier,
+        TokenKind::Whitespace | TokenKind::Symbol => Style::default(),
+    }
+}
+
+/// Renders the script list, showing each script's bound database after its name
+fn render_script_list(f: &mut Frame, app: &mut App, block: Block, area: Rect) {
+    let theme = &app.theme;
+    let items: Vec<ListItem> = app
+        .sql_files
+        .iter()
+        .map(|full_path| {
+            let path = Path::new(full_path);
+            let relative = path.strip_prefix(&app.script_dir_path).unwrap_or(path);
+            if app.is_folder(full_path) {
+                let marker = if app.collapsed_folders.contains(full_path) {
+                    "▸"
+                } else {
+                    "▾"
+                };
+                let name = path.file_name().unwrap_or_default().to_string_lossy();
+                let indent = "  ".repeat(relative.components().count() - 1);
+                return ListItem::new(Line::styled(
+                    format!("{}{} {}/", indent, marker, name),
+                    theme.folder,
+                ));
+            }
+            let filename_stem = path
+                .file_stem()
+                .unwrap_or_else(|| OsStr::new("invalid_filename"))
+                .to_string_lossy()
+                .to_string();
+            // Search results are a flat list, so show the folder instead of indenting
+            let folder = relative.parent().filter(|p| !p.as_os_str().is_empty());
+            let mut spans = if app.search_query.is_empty() {
+                let indent = "  ".repeat(relative.components().count());
+                vec![Span::raw(indent)]
+            } else {
+                folder
+                    .map(|folder| Span::styled(format!("{}/", folder.display()), theme.dim))
+                    .into_iter()
+                    .collect()
+            };
+            match app.search_matches.get(full_path) {
+                Some(positions) => {
+                    spans.extend(highlight_matches(&filename_stem, positions, theme))
+                }
+                None => spans.push(Span::raw(filename_stem)),
+            }
+            if let Some(name) = app.script_databases.get(full_path) {
+                let style = if app.is_known_database(name) {
+                    theme.dim
+                } else {
+                    theme.error
+                };
+                spans.push(Span::styled(format!(" @{}", name), style));
+            }
+            ListItem::new(Line::from(spans))
+        })
+        .collect();
+
+    let list = List::new(items)
+        .block(block)
+        .highlight_style(theme.selection)
+        .highlight_symbol(">> ");
+    f.render_stateful_widget(list, area, &mut app.list_state);
+}
+
+/// Splits `text` into spans, with the chars at `positions` highlighted
+fn highlight_matches(text: &str, positions: &[usize], theme: &Theme) -> Vec<Span<'static>> {
+    let matched_style = theme
+        .accent
+        .add_modifier(Modifier::BOLD | Modifier::UNDERLINED);
+    let mut spans = Vec::new();
+    let mut run = String::new();
+    let mut run_matched = false;
+    for (i, c) in text.chars().enumerate() {
+        let matched = positions.contains(&i);
+        if matched != run_matched && !run.is_empty() {
+            let style = if run_matched {
+                matched_style
+            } else {
+                Style::default()
+            };
+            spans.push(Span::styled(std::mem::take(&mut run), style));
+        }
+        run_matched = matched;
+        run.push(c);
+    }
+    if !run.is_empty() {
+        let style = if run_matched {
+            matched_style
+        } else {
+            Style::default()
+        };
+        spans.push(Span::styled(run, style));
+    }
+    spans
+}
+
+/// The left pane's title: both tab names, with the active one highlighted
+fn left_pane_tabs(left_pane: LeftPane, theme: &Theme) -> Line<'static> {
+    Line::from(vec![
+        Span::styled(
+            "SQL Scripts",
+            tab_style(left_pane == LeftPane::Scripts, theme),
+        ),
+        Span::raw("|"),
+        Span::styled("Schema", tab_style(left_pane == LeftPane::Schema, theme)),
+    ])
+}
+
+fn tab_style(active: bool, theme: &Theme) -> Style {
+    if active {
+        Style::default().add_modifier(Modifier::BOLD | Modifier::UNDERLINED)
+    } else {
+        theme.dim
+    }
+}
+
+/// Renders the Results pane: a status line above either the row grid or a statement summary
+fn render_results(f: &mut Frame, app: &mut App, area: Rect) {
+    let theme = app.theme.clone();
+    let title = if app.results.len() > 1 {
+        format!("Results [{}/{}]", app.result_index + 1, app.results.len())
+    } else {
+        "Results".to_string()
+    };
+    let mut results_block = Block::default()
+        .borders(Borders::ALL)
+        .title(title)
+        .border_style(focus_style(app.focus == Focus::Results, &theme));
+    if let Some(transaction) = &app.transaction {
+        results_block = results_block.title_bottom(Line::styled(
+            format!(
+                " TRANSACTION OPEN on {}  [{}] commit or roll back ",
+                transaction.database.name,
+                app.keymap.describe(Action::RunInTransaction)
+            ),
+            theme.transaction.add_modifier(Modifier::BOLD),
+        ));
+    }
+    let inner = results_block.inner(area);
+    f.render_widget(results_block, area);
+
+    let status_text = match &app.running {
+        Some(running) => {
+            let elapsed = running.started.elapsed();
+            let frame = (elapsed.as_millis() / 100) as usize % SPINNER_FRAMES.len();
+            let script = Path::new(&running.script_path)
+                .file_stem()
+                .unwrap_or_else(|| OsStr::new("script"))
+                .to_string_lossy()
+                .to_string();
+            if running.cancelled {
+                format!("{} Cancelling {}…", SPINNER_FRAMES[frame], script)
+            } else {
+                format!(
+                    "{} Running {}… {:.1}s  [{}/{}] cancel",
+                    SPINNER_FRAMES[frame],
+                    script,
+                    elapsed.as_secs_f64(),
+                    app.keymap.first_key(Action::Back),
+                    app.keymap.first_key(Action::CancelRun)
+                )
+            }
+        }
+        None => app.query_result.clone(),
+    };
+    let status_height = status_line_count(&status_text, inner.width);
+    let chunks = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([Constraint::Length(status_height), Constraint::Min(0)])
+        .split(inner);
+    let status_style = if app.running.is_none() && app.status_is_failure() {
+        theme.error
+    } else {
+        Style::default()
+    };
+    let status = Paragraph::new(status_text)
+        .style(status_style)
+        .wrap(Wrap { trim: false });
+    f.render_widget(status, chunks[0]);
+
+    let Some(result) = app.results.get(app.result_index) else {
+        return;
+    };
+    let mut body = chunks[1];
+    if let Some(stats) = &result.stats {
+        let footer_chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(body);
+        body = footer_chunks[0];
+        f.render_widget(Paragraph::new(stats_line(stats, &theme)), footer_chunks[1]);
+    }
+    match &result.output {
+        StatementOutput::Rows(result_set) => render_result_table(
+            f,
+            result_set,
+            &mut app.result_view,
+            &app.value_format,
+            &theme,
+            body,
+        ),
+        StatementOutput::Changes(changes) => {
+            let summary = format!("{}\n\n{} rows affected.", result.sql, changes);
+            f.render_widget(Paragraph::new(summary).wrap(Wrap { trim: false }), body);
+        }
+        StatementOutput::Done => {
+            let summary = format!("{}\n\nStatement executed.", result.sql);
+            f.render_widget(Paragraph::new(summary).wrap(Wrap { trim: false }), body);
+        }
+        StatementOutput::Plan(plan) => {
+            let plan_chunks = Layout::default()
+                .direction(Direction::Vertical)
+                .constraints([Constraint::Length(1), Constraint::Min(0)])
+                .split(body);
+            let tabs = Line::from(vec![
+                Span::styled("Query Plan", tab_style(!app.show_bytecode, &theme)),
+                Span::raw("|"),
+                Span::styled("Bytecode", tab_style(app.show_bytecode, &theme)),
+            ]);
+            f.render_widget(Paragraph::new(tabs), plan_chunks[0]);
+            if app.show_bytecode {
+                render_result_table(
+                    f,
+                    &plan.bytecode,
+                    &mut app.result_view,
+                    &app.value_format,
+                    &theme,
+                    plan_chunks[1],
+                );
+            } else {
+                f.render_widget(
+                    Paragraph::new(plan_lines(&result.sql, plan, &theme)),
+                    plan_chunks[1],
+                );
+            }
+        }
+    }
+}
+
+/// The footer under a statement's results: its timing, row counts and SQLite's counters
+fn stats_line(stats: &StatementStats, theme: &Theme) -> Line<'static> {
+    let dim = theme.dim;
+    // Counters that usually point at a missing index stand out when they're non-zero
+    let counter = |label: &str, value: i32| {
+        let style = if value > 0 { theme.accent } else { dim };
+        Span::styled(format!("  {} {}", label, value), style)
+    };
+    Line::from(vec![
+        Span::styled(
+            format!(
+                "{}  {} row(s)  {} changed",
+                format_duration(stats.duration),
+                stats.rows_returned,
+                stats.changes
+            ),
+            dim,
+        ),
+        counter("fullscan steps", stats.fullscan_steps),
+        counter("sorts", stats.sorts),
+        counter("autoindex", stats.autoindex_rows),
+        Span::styled(format!("  vm steps {}", stats.vm_steps), dim),
+    ])
+}
+
+/// A duration in the unit that keeps it readable: µs, ms or s
+fn format_duration(duration: Duration) -> String {
+    let micros = duration.as_micros();
+    if micros < 1_000 {
+        format!("{} µs", micros)
+    } else if micros < 1_000_000 {
+        format!("{:.1} ms", micros as f64 / 1_000.0)
+    } else {
+        format!("{:.2} s", duration.as_secs_f64())
+    }
+}
+
+/// The statement followed by its query plan drawn as a tree, with full table scans in red
+fn plan_lines<'a>(sql: &'a str, plan: &'a QueryPlan, theme: &Theme) -> Vec<Line<'a>> {
+    let mut lines: Vec<Line> = sql
+        .lines()
+        .map(|line| Line::styled(line, theme.dim))
+        .collect();
+    lines.push(Line::raw(""));
+    if plan.steps.is_empty() {
+        lines.push(Line::raw("No query plan for this statement."));
+        return lines;
+    }
+    lines.push(Line::styled(
+        "QUERY PLAN",
+        Style::default().add_modifier(Modifier::BOLD),
+    ));
+    for (prefix, step) in plan.tree() {
+        let style = if is_full_scan(&step.detail) {
+            theme.error.add_modifier(Modifier::BOLD)
+        } else if uses_temp_btree(&step.detail) {
+            theme.accent
+        } else {
+            Style::default()
+        };
+        lines.push(Line::from(vec![
+            Span::styled(prefix, theme.dim),
+            Span::styled(step.detail.as_str(), style),
+        ]));
+    }
+    lines
+}
+
+/// Draws the visible window of a result set, scrolling so the cursor cell stays on screen
+fn render_result_table(
+    f: &mut Frame,
+    result_set: &ResultSet,
+    view: &mut ResultView,
+    value_format: &ValueFormat,
+    theme: &Theme,
+    area: Rect,
+) {
+    if result_set.columns.is_empty() || area.height == 0 {
+        return;
+    }
+
+    // Cap every column to half the pane so one huge TEXT cell can't push the rest off screen
+    let max_width = (area.width / 2).max(MIN_COLUMN_WIDTH) as usize;
+    let widths: Vec<u16> = view
+        .column_widths
+        .iter()
+        .map(|&w| w.clamp(1, max_width) as u16)
+        .collect();
+
+    // Vertical scrolling: the header row is always drawn, the rest is the page
+    let page_height = area.height.saturating_sub(1) as usize;
+    view.page_height = page_height;
+    if view.row < view.row_offset {
+        view.row_offset = view.row;
+    } else if page_height > 0 && view.row >= view.row_offset + page_height {
+        view.row_offset = view.row + 1 - page_height;
+    }
+
+    // Horizontal scrolling: shift the first visible column until the cursor column fits
+    if view.column < view.column_offset {
+        view.column_offset = view.column;
+    }
+    while view.column_offset < view.column
+        && span_width(&widths[view.column_offset..=view.column]) > area.width
+    {
+        view.column_offset += 1;
+    }
+    let mut visible_columns = Vec::new();
+    let mut used_width = 0u16;
+    for (index, &width) in widths.iter().enumerate().skip(view.column_offset) {
+        if !visible_columns.is_empty() && used_width + width > area.width {
+            break;
+        }
+        used_width += width + COLUMN_SPACING;
+        visible_columns.push(index);
+    }
+
+    let header = Row::new(
+        visible_columns
+            .iter()
+            .map(|&c| Cell::from(result_set.columns[c].as_str())),
+    )
+    .style(theme.header);
+
+    let window_end = (view.row_offset + page_height).min(result_set.rows.len());
+    let rows = result_set.rows[view.row_offset.min(window_end)..window_end]
+        .iter()
+        .map(|row| {
+            Row::new(visible_columns.iter().map(|&c| {
+                let value = &row[c];
+                let cell = Cell::from(render_inline(value.into(), value_format));
+                if matches!(value, Value::Null) {
+                    cell.style(theme.null)
+                } else {
+                    cell
+                }
+            }))
+        });
+
+    let table = Table::new(
+        rows,
+        visible_columns
+            .iter()
+            .map(|&c| Constraint::Length(widths[c])),
+    )
+    .header(header)
+    .column_spacing(COLUMN_SPACING)
+    .row_highlight_style(theme.cursor_row)
+    .cell_highlight_style(theme.cursor);
+
+    let mut table_state = TableState::default()
+        .with_selected(Some(view.row - view.row_offset))
+        .with_selected_column(visible_columns.iter().position(|&c| c == view.column));
+    f.render_stateful_widget(table, area, &mut table_state);
+}
+
+/// Builds the inspector popup's title and body for the cursor cell or row
+fn inspector_content(app: &App) -> (String, String) {
+    let Some(result_set) = app.current_result_set() else {
+        return ("Inspector".to_string(), String::new());
+    };
+    let view = &app.result_view;
+    let Some(row) = result_set.rows.get(view.row) else {
+        return ("Inspector".to_string(), String::new());
+    };
+    match app.inspector.target {
+        InspectTarget::Cell => {
+            let column = &result_set.columns[view.column];
+            let title = format!("{} (row {})", column, view.row + 1);
+            (
+                title,
+                render_detail((&row[view.column]).into(), &app.value_format),
+            )
+        }
+        InspectTarget::Row => {
+            let name_width = result_set
+                .columns
+                .iter()
+                .map(|name| name.chars().count())
+                .max()
+                .unwrap_or(0);
+            let lines: Vec<String> = result_set
+                .columns
+                .iter()
+                .zip(row)
+                .map(|(name, value)| {
+                    format!(
+                        "{:>width$}: {}",
+                        name,
+                        render_inline(value.into(), &app.value_format),
+                        width = name_width
+                    )
+                })
+                .collect();
+            (format!("Row {}", view.row + 1), lines.join("\n"))
+        }
+    }
+}
+
+/// Total width taken by a run of adjacent columns, including the gaps between them
+fn span_width(widths: &[u16]) -> u16 {
+    widths.iter().sum::<u16>() + COLUMN_SPACING * widths.len().saturating_sub(1) as u16
+}
+
+/// Number of lines the status message needs, capped so the grid keeps most of the pane
+fn status_line_count(message: &str, width: u16) -> u16 {
+    let width = width.max(1) as usize;
+    let lines: usize = message
+        .lines()
+        .map(|line| line.chars().count().div_ceil(width).max(1))
+        .sum();
+    lines.clamp(1, MAX_STATUS_LINES) as u16
+}
+
+/// Border style marking which pane currently has focus
+fn focus_style(focused: bool, theme: &Theme) -> Style {
+    if focused {
+        theme.focus
+    } else {
+        Style::default()
+    }
+}
+
 /// Helper function to create a centered rectangle for popups
 fn centered_rect(percent_x: u16, height: u16, r: Rect) -> Rect {
     let (top_padding, bottom_padding) = {
@@ -0,0 +1,116 @@
+use rusqlite::types::ValueRef;
+
+/// Number of leading bytes shown when a blob is rendered inline
+const BLOB_PREVIEW_BYTES: usize = 8;
+/// Bytes per line in the inspector's hex dump
+const HEX_DUMP_WIDTH: usize = 16;
+
+/// Options controlling how SQLite values are turned into display text
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ValueFormat {
+    /// Fixed number of decimal places for REAL values; `None` prints the shortest exact form
+    pub float_precision: Option<usize>,
+}
+
+/// Renders a single SQLite value for display in the results grid
+pub fn render_value(value: ValueRef, format: &ValueFormat) -> String {
+    match value {
+        ValueRef::Null => "NULL".to_string(),
+        ValueRef::Integer(i) => i.to_string(),
+        ValueRef::Real(f) => render_real(f, format),
+        ValueRef::Text(bytes) => String::from_utf8_lossy(bytes).to_string(),
+        ValueRef::Blob(bytes) => render_blob_preview(bytes),
+    }
+}
+
+/// Renders a value on a single line, for grid cells and other one-line contexts
+pub fn render_inline(value: ValueRef, format: &ValueFormat) -> String {
+    render_value(value, format).replace(['\n', '\r', '\t'], " ")
+}
+
+/// Renders a value in full for the inspector: JSON is pretty-printed and blobs become a hex dump
+pub fn render_detail(value: ValueRef, format: &ValueFormat) -> String {
+    match value {
+        ValueRef::Text(bytes) => {
+            let text = String::from_utf8_lossy(bytes);
+            pretty_json(&text).unwrap_or_else(|| text.to_string())
+        }
+        ValueRef::Blob(bytes) => format!("{} bytes\n\n{}", bytes.len(), hex_dump(bytes)),
+        _ => render_value(value, format),
+    }
+}
+
+/// Pretty-prints `text` when it holds a JSON object or array
+fn pretty_json(text: &str) -> Option<String> {
+    let trimmed = text.trim_start();
+    if !(trimmed.starts_with('{') || trimmed.starts_with('[')) {
+        return None;
+    }
+    let json: serde_json::Value = serde_json::from_str(text).ok()?;
+    serde_json::to_string_pretty(&json).ok()
+}
+
+/// Formats bytes as `offset  hex bytes  |ascii|` lines, like `hexdump -C`
+pub fn hex_dump(bytes: &[u8]) -> String {
+    let mut output = String::new();
+    for (line_index, chunk) in bytes.chunks(HEX_DUMP_WIDTH).enumerate() {
+        let hex: Vec<String> = chunk.iter().map(|b| format!("{:02x}", b)).collect();
+        let ascii: String = chunk
+            .iter()
+            .map(|&b| {
+                if b.is_ascii_graphic() || b == b' ' {
+                    b as char
+                } else {
+                    '.'
+                }
+            })
+            .collect();
+        output.push_str(&format!(
+            "{:08x}  {:<width$}  |{}|\n",
+            line_index * HEX_DUMP_WIDTH,
+            hex.join(" "),
+            ascii,
+            width = HEX_DUMP_WIDTH * 3 - 1
+        ));
+    }
+    output
+}
+
+fn render_real(f: f64, format: &ValueFormat) -> String {
+    match format.float_precision {
+        Some(precision) => format!("{:.*}", precision, f),
+        None => {
+            let magnitude = f.abs();
+            let rendered = if magnitude != 0.0 && !(1e-4..1e15).contains(&magnitude) {
+                format!("{:e}", f)
+            } else {
+                f.to_string()
+            };
+            // Keep REALs visually distinct from INTEGERs, the way the sqlite3 shell does
+            if f.is_finite() && !rendered.contains(['.', 'e']) {
+                format!("{}.0", rendered)
+            } else {
+                rendered
+            }
+        }
+    }
+}
+
+fn render_blob_preview(bytes: &[u8]) -> String {
+    let preview: Vec<String> = bytes
+        .iter()
+        .take(BLOB_PREVIEW_BYTES)
+        .map(|b| format!("{:02x}", b))
+        .collect();
+    let ellipsis = if bytes.len() > BLOB_PREVIEW_BYTES {
+        " …"
+    } else {
+        ""
+    };
+    format!(
+        "<blob {} bytes: {}{}>",
+        bytes.len(),
+        preview.join(" "),
+        ellipsis
+    )
+}
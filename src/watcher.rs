@@ -0,0 +1,50 @@
+use notify::{Event, EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::{
+    path::{Component, Path, PathBuf},
+    sync::mpsc::{self, Receiver},
+};
+
+/// Watches the script directory (inotify on Linux) so outside edits show up without a restart
+pub struct ScriptWatcher {
+    // Dropping the watcher stops the events, so it is kept alive alongside the receiver
+    _watcher: RecommendedWatcher,
+    script_dir_path: PathBuf,
+    receiver: Receiver<notify::Result<Event>>,
+}
+
+impl ScriptWatcher {
+    pub fn new(script_dir_path: &Path) -> notify::Result<Self> {
+        let (sender, receiver) = mpsc::channel();
+        let mut watcher = notify::recommended_watcher(sender)?;
+        watcher.watch(script_dir_path, RecursiveMode::Recursive)?;
+        Ok(Self {
+            _watcher: watcher,
+            script_dir_path: script_dir_path.to_path_buf(),
+            receiver,
+        })
+    }
+
+    /// Drains pending events and reports whether any of them could change the script list
+    /// or a script's contents. Reads, and churn inside hidden folders such as `.git`, are ignored.
+    pub fn has_changes(&self) -> bool {
+        let mut changed = false;
+        for event in self.receiver.try_iter().flatten() {
+            if matches!(event.kind, EventKind::Access(_)) {
+                continue;
+            }
+            changed |= event.paths.iter().any(|path| {
+                // The script directory itself may live under a hidden one, like ~/.config
+                let relative = path.strip_prefix(&self.script_dir_path).unwrap_or(path);
+                !is_hidden(relative)
+            });
+        }
+        changed
+    }
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.components().any(|component| match component {
+        Component::Normal(name) => name.to_string_lossy().starts_with('.'),
+        _ => false,
+    })
+}